@@ -6,7 +6,7 @@ use std::fs::{self, DirEntry};
 use std::path::Path;
 
 fn main() {
-    #[cfg(not(feature = "docs_only"))]
+    #[cfg(all(feature = "ultralight", not(feature = "docs_only")))]
     {
         // ensure runtime resources exist - for examples & local tests
         #[cfg(not(feature = "ultralight-resources"))]
@@ -1,12 +1,10 @@
 use iced::{
-    time,
     widget::{button, column, container, row, text},
     Element, Length, Subscription, Task,
 };
-use iced_webview::{Action, PageType, Ultralight, WebView};
-use std::time::Duration;
+use iced_webview::{window_scale_factor, Action, PageType, Session, Ultralight, WebView};
 
-static URL: &'static str = "https://docs.rs/iced/latest/iced/index.html";
+static URL: &str = "https://docs.rs/iced/latest/iced/index.html";
 
 fn main() -> iced::Result {
     iced::application("An embedded web view", App::update, App::view)
@@ -36,7 +34,8 @@ impl App {
     fn new() -> (Self, Task<Message>) {
         let webview = WebView::new()
             .on_create_view(Message::WebviewCreated)
-            .on_url_change(Message::UrlChanged);
+            .on_url_change(Message::UrlChanged)
+            .zoom_shortcuts();
         (
             Self {
                 webview,
@@ -45,19 +44,24 @@ impl App {
                 num_views: 0,
                 current_view: None,
             },
-            // Create the first webview so its available once toggled
-            Task::done(Message::CreateWebview),
+            Task::batch([
+                // Draw pages at the window's scale factor so they stay sharp
+                window_scale_factor().map(|scale| Message::WebView(Action::SetScaleFactor(scale))),
+                // Create the first webview so its available once toggled
+                Task::done(Message::CreateWebview),
+            ]),
         )
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::WebView(msg) => self.webview.update(msg),
-            Message::CreateWebview => self
-                .webview
-                .update(Action::CreateView(PageType::Url(URL.to_string()))),
+            Message::CreateWebview => self.webview.update(Action::CreateView(
+                PageType::Url(URL.to_string()),
+                Session::Default,
+            )),
             Message::WebviewCreated => {
-                if self.current_view == None {
+                if self.current_view.is_none() {
                     // if its the first tab change to it, after that require switching manually
                     return Task::done(Message::CycleWebview);
                 }
@@ -88,7 +92,7 @@ impl App {
         }
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let mut column = column![row![
             text(if !self.show_webview {
                 "Click the button to open a webview"
@@ -116,8 +120,6 @@ impl App {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        time::every(Duration::from_millis(10))
-            .map(|_| Action::Update)
-            .map(Message::WebView)
+        self.webview.subscription().map(Message::WebView)
     }
 }
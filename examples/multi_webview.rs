@@ -1,16 +1,14 @@
 use iced::{
-    time,
     widget::{column, container, row, text},
     Element, Length, Subscription, Task,
 };
 use iced_webview::{
     advanced::{Action, WebView},
-    PageType, Ultralight, ViewId,
+    window_scale_factor, PageType, Session, Ultralight, ViewId,
 };
-use std::time::Duration;
 
-static URL1: &'static str = "https://docs.rs/iced/latest/iced/index.html";
-static URL2: &'static str = "https://github.com/LegitCamper/iced_webview";
+static URL1: &str = "https://docs.rs/iced/latest/iced/index.html";
+static URL2: &str = "https://github.com/LegitCamper/iced_webview";
 
 fn main() -> iced::Result {
     iced::application("An multi webview application", App::update, App::view)
@@ -38,10 +36,19 @@ impl App {
                 webviews: (None, None),
             },
             Task::chain(
-                Task::done(Action::CreateView(PageType::Url(URL1.to_string())))
+                window_scale_factor().map(|scale| Message::WebView(Action::SetScaleFactor(scale))),
+                Task::chain(
+                    Task::done(Action::CreateView(
+                        PageType::Url(URL1.to_string()),
+                        Session::Default,
+                    ))
                     .map(Message::WebView),
-                Task::done(Action::CreateView(PageType::Url(URL2.to_string())))
+                    Task::done(Action::CreateView(
+                        PageType::Url(URL2.to_string()),
+                        Session::Default,
+                    ))
                     .map(Message::WebView),
+                ),
             ),
         )
     }
@@ -51,9 +58,9 @@ impl App {
         tasks.push(match message {
             Message::WebView(msg) => self.webview.update(msg),
             Message::CreatedNewWebView(view_id) => {
-                if self.webviews.0 == None {
+                if self.webviews.0.is_none() {
                     self.webviews.0 = Some(view_id);
-                } else if self.webviews.1 == None {
+                } else if self.webviews.1.is_none() {
                     self.webviews.1 = Some(view_id);
                 }
                 Task::none()
@@ -68,7 +75,7 @@ impl App {
         Task::batch(tasks)
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let Some(view1) = self.webviews.0 else {
             return text("loading").into();
         };
@@ -91,8 +98,6 @@ impl App {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch([time::every(Duration::from_millis(10))
-            .map(|_| Action::UpdateAll)
-            .map(Message::WebView)])
+        self.webview.subscription().map(Message::WebView)
     }
 }
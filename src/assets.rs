@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Files bundled into the app, served by engines under [`Assets::URL`]
+///
+/// Paths are relative to the root of the bundle, so `index.html` is shown by visiting
+/// `file:///app/index.html`, which lets a single binary carry its whole web UI
+///
+/// ```rust
+/// let assets = iced_webview::Assets::new()
+///     .with("index.html", b"<link rel=\"stylesheet\" href=\"style.css\"><h1>Hello</h1>")
+///     .with("style.css", b"h1 { color: red }");
+/// assert_eq!(assets.get_url("file:///app/style.css"), Some(&b"h1 { color: red }"[..]));
+/// assert_eq!(iced_webview::Assets::mime_type("style.css"), "text/css");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Assets {
+    // shared, since every view of an engine keeps a copy
+    files: Arc<HashMap<String, Cow<'static, [u8]>>>,
+}
+
+impl Assets {
+    /// The url the assets are served under
+    pub const URL: &'static str = "file:///app/";
+
+    /// Creates an empty set of assets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing any file already at the path
+    pub fn with(mut self, path: &str, bytes: impl Into<Cow<'static, [u8]>>) -> Self {
+        self.insert(path, bytes);
+        self
+    }
+
+    /// Adds a file, replacing any file already at the path
+    pub fn insert(&mut self, path: &str, bytes: impl Into<Cow<'static, [u8]>>) {
+        Arc::make_mut(&mut self.files).insert(normalize(path).to_string(), bytes.into());
+    }
+
+    /// Whether no files have been added
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Gets a file by its path in the bundle, a directory gets its `index.html`
+    pub fn get(&self, path: &str) -> Option<&[u8]> {
+        let path = normalize(path);
+        match path.is_empty() || path.ends_with('/') {
+            true => self.files.get(&format!("{path}index.html")),
+            false => self.files.get(path),
+        }
+        .map(|bytes| bytes.as_ref())
+    }
+
+    /// Gets the file a url points to, if it is under [`Assets::URL`]
+    pub fn get_url(&self, url: &str) -> Option<&[u8]> {
+        self.get(Self::path(url)?)
+    }
+
+    /// The path in the bundle a url points to, if it is under [`Assets::URL`]
+    pub fn path(url: &str) -> Option<&str> {
+        let path = url.strip_prefix(Self::URL)?;
+        Some(path.split(['?', '#']).next().unwrap_or_default())
+    }
+
+    /// The url a file in the bundle is served at
+    pub fn url(path: &str) -> String {
+        format!("{}{}", Self::URL, normalize(path))
+    }
+
+    /// Guesses a file's MIME type from its extension,
+    /// falling back to `application/octet-stream`
+    pub fn mime_type(path: &str) -> &'static str {
+        let extension = path
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("html" | "htm") => "text/html",
+            Some("css") => "text/css",
+            Some("js" | "mjs") => "text/javascript",
+            Some("json" | "map") => "application/json",
+            Some("txt") => "text/plain",
+            Some("xml") => "application/xml",
+            Some("svg") => "image/svg+xml",
+            Some("png") => "image/png",
+            Some("jpg" | "jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            Some("ico") => "image/x-icon",
+            Some("woff") => "font/woff",
+            Some("woff2") => "font/woff2",
+            Some("ttf") => "font/ttf",
+            Some("otf") => "font/otf",
+            Some("wasm") => "application/wasm",
+            Some("mp3") => "audio/mpeg",
+            Some("mp4") => "video/mp4",
+            Some("webm") => "video/webm",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+fn normalize(path: &str) -> &str {
+    path.trim_start_matches('/')
+}
+
+/// Builds [`Assets`] from files embedded into the binary at compile time
+///
+/// Each file is given as its path in the bundle and the path to embed it from,
+/// which like [`include_bytes!`] is relative to the file the macro is used in
+///
+/// ```rust,ignore
+/// let assets = iced_webview::include_assets! {
+///     "index.html" => "../web/index.html",
+///     "app.js" => "../web/app.js",
+/// };
+/// ```
+#[macro_export]
+macro_rules! include_assets {
+    ($($path:literal => $file:literal),* $(,)?) => {
+        $crate::Assets::new()$(.with($path, &include_bytes!($file)[..]))*
+    };
+}
@@ -1,13 +1,26 @@
-use crate::ImageInfo;
+use crate::{Assets, Error, ImageInfo};
 use iced::keyboard;
 use iced::mouse::{self, Interaction};
 use iced::Point;
+use iced::Rectangle;
 use iced::Size;
+use iced::Subscription;
 
 /// A Ultralight implementation of Engine
 #[cfg(feature = "ultralight")]
 pub mod ultralight;
 
+/// A headless, in-memory implementation of Engine for tests
+#[cfg(feature = "mock")]
+pub mod mock;
+
+/// Runs another implementation of Engine on its own thread
+pub mod threaded;
+
+/// A pure Rust implementation of Engine for local html content
+#[cfg(feature = "pure-html")]
+pub mod pure_html;
+
 /// Creation of new pages to be of a html type or a url
 #[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub enum PageType {
@@ -17,6 +30,262 @@ pub enum PageType {
     Html(String),
 }
 
+/// Where a view keeps its cookies, local storage and cache
+///
+/// Views share them only within a session, so users can be logged in separately in two views
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Session {
+    /// The engine's own session, shared by every view not given another
+    #[default]
+    Default,
+    /// A named session saved to disk, in a directory named after it under the engine's cache path
+    Persistent(String),
+    /// A named session kept only in memory, which is lost once the engine is dropped
+    Private(String),
+}
+
+/// The kinds of data [`Engine::clear_data`] removes from a session
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BrowsingData {
+    /// Cookies, which keep users logged in
+    pub cookies: bool,
+    /// Pages, images and other responses kept to load faster
+    pub cache: bool,
+    /// What pages saved in `localStorage` and `sessionStorage`
+    pub local_storage: bool,
+}
+
+impl BrowsingData {
+    /// Every kind of data, for resetting a session completely
+    pub const ALL: Self = Self {
+        cookies: true,
+        cache: true,
+        local_storage: true,
+    };
+}
+
+/// Something that happened in a view, collected with [`Engine::take_events`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViewEvent {
+    /// The view started loading a page
+    LoadStarted(String),
+    /// The page's document has been parsed, so scripts can use the DOM
+    DomReady(String),
+    /// The page and its resources finished loading
+    LoadFinished(String),
+    /// The page failed to load
+    LoadFailed(LoadFailure),
+    /// The back/forward history of the view changed
+    HistoryChanged,
+    /// A script from [`Engine::evaluate_script`] finished
+    ScriptEvaluated(ScriptResult),
+    /// The page sent a message with `window.iced.postMessage`
+    Message(String),
+    /// The page logged to its console, or threw an exception nothing caught
+    ConsoleMessage(ConsoleMessage),
+    /// The page tried to navigate to a url and is waiting for the app to decide,
+    /// see [`Engine::intercept_navigation`]
+    NavigationRequested(String, NavigationKind),
+    /// The page asked to open a url in a new window,
+    /// like with a `target="_blank"` link or `window.open`
+    NewWindowRequested(String, Opener),
+    /// The page opened a dialog with `alert`, or with `window.iced`'s `alert`, `confirm` or `prompt`,
+    /// answer it with [`Engine::answer_dialog`]
+    DialogRequested(DialogId, Dialog),
+    /// The page requested a url with the scheme set with [`Engine::set_scheme`],
+    /// answer it with [`Engine::respond`]
+    Request(RequestId, Request),
+    /// The whole page was rendered into one image by [`Engine::capture_full_page`]
+    FullPageCaptured(ImageInfo),
+    /// A search from [`Engine::find`] finished
+    FindResult(FindResult),
+}
+
+/// The outcome of a script, either its result as a string or the message of the exception it threw
+pub type ScriptResult = Result<String, String>;
+
+/// Why a page failed to load
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoadFailure {
+    /// The url that failed to load
+    pub url: String,
+    /// The error code, its meaning is specific to each engine
+    pub code: i32,
+    /// A human readable description of the error
+    pub description: String,
+}
+
+/// The outcome of [`Engine::find`], telling which search it belongs to so late results can be told apart
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FindResult {
+    /// The text that was searched for
+    pub query: String,
+    /// Whether letters had to match in case
+    pub case_sensitive: bool,
+    /// The selected match, counting from 1, or 0 if nothing matched
+    pub active_match: usize,
+    /// How many matches there are in total
+    pub total_matches: usize,
+}
+
+/// A message a page logged to its console
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConsoleMessage {
+    /// How severe the message is, uncaught exceptions are [`ConsoleLevel::Error`]
+    pub level: ConsoleLevel,
+    /// The logged text
+    pub message: String,
+    /// The url of the script or document that logged the message, empty if unknown
+    pub source: String,
+    /// The line in `source` that logged the message, 0 if unknown
+    pub line: u32,
+}
+
+impl ConsoleMessage {
+    /// Forwards the message to the `log` crate under the `iced_webview::console` target,
+    /// which `tracing` subscribers can also collect
+    #[cfg(feature = "log")]
+    pub fn log(&self, id: ViewId) {
+        let level = match self.level {
+            ConsoleLevel::Debug => log::Level::Debug,
+            ConsoleLevel::Log | ConsoleLevel::Info => log::Level::Info,
+            ConsoleLevel::Warning => log::Level::Warn,
+            ConsoleLevel::Error => log::Level::Error,
+        };
+        log::log!(
+            target: "iced_webview::console",
+            level,
+            "view {id}: {} ({}:{})",
+            self.message,
+            self.source,
+            self.line
+        );
+    }
+}
+
+/// The level of a [`ConsoleMessage`], matching the `console` method that logged it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConsoleLevel {
+    /// `console.debug`
+    Debug,
+    /// `console.log`
+    Log,
+    /// `console.info`
+    Info,
+    /// `console.warn`
+    Warning,
+    /// `console.error`, and exceptions nothing caught
+    Error,
+}
+
+/// What started a navigation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NavigationKind {
+    /// The app navigated, like with `Action::GoToUrl`
+    GoTo,
+    /// The user clicked a link
+    LinkClick,
+    /// The page navigated by itself, like a redirect, a submitted form or a script
+    Redirect,
+}
+
+/// Where a request for a new window came from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Opener {
+    /// The url of the page that asked for the window
+    pub url: String,
+    /// Whether the page opened it with `window.open`, rather than a link
+    pub is_popup: bool,
+    /// The position and size `window.open` asked for, if any
+    pub rect: Option<Rectangle<i32>>,
+}
+
+/// Identifies a [`Dialog`] within its view, so it can be answered with [`Engine::answer_dialog`]
+pub type DialogId = u64;
+
+/// A JavaScript dialog a page opened
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dialog {
+    /// Which kind of dialog it is
+    pub kind: DialogKind,
+    /// The text the page shows in the dialog
+    pub message: String,
+}
+
+/// The kind of a [`Dialog`], matching the function that opened it
+///
+/// A page's own blocking `confirm` and `prompt` can not wait for the app, so only the promises
+/// `window.iced.confirm` and `window.iced.prompt` return are resolved with its answer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DialogKind {
+    /// `alert(message)` or `window.iced.alert(message)`, which only needs to be acknowledged
+    Alert,
+    /// `window.iced.confirm(message)`, which resolves to whether it was accepted
+    Confirm,
+    /// `window.iced.prompt(message, default)`, which asks for text, starting from the default,
+    /// and resolves to it or to `null` if dismissed
+    Prompt(String),
+}
+
+/// How the user answered a [`Dialog`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DialogAnswer {
+    /// Closed or cancelled the dialog
+    Dismiss,
+    /// Pressed OK, keeping the default text of a prompt
+    Accept,
+    /// Pressed OK on a prompt after entering text
+    Text(String),
+}
+
+/// Identifies a [`Request`] within its view, so it can be answered with [`Engine::respond`]
+pub type RequestId = u64;
+
+/// A request a page made to the app's own scheme, see [`Engine::set_scheme`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    /// The full url, like `app://api/users`
+    pub url: String,
+    /// The HTTP method, like `GET`
+    pub method: String,
+    /// The request's headers, in the order the page set them
+    pub headers: Vec<(String, String)>,
+    /// The request's body, empty if it had none
+    pub body: Vec<u8>,
+}
+
+/// The app's answer to a [`Request`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    /// The HTTP status code
+    pub status: u16,
+    /// The response's headers
+    pub headers: Vec<(String, String)>,
+    /// The response's body
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Creates a response with a status, a `Content-Type` and a body
+    pub fn new(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: body.into(),
+        }
+    }
+
+    /// Creates a successful response
+    pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self::new(200, content_type, body)
+    }
+
+    /// Creates a response saying nothing was found at the url
+    pub fn not_found() -> Self {
+        Self::new(404, "text/plain", "Not Found")
+    }
+}
+
 /// Enables browser engines to display their images in different formats
 pub enum PixelFormat {
     /// RGBA
@@ -25,54 +294,154 @@ pub enum PixelFormat {
     Bgra,
 }
 
+/// The tallest image [`Engine::capture_full_page`] renders, in physical pixels,
+/// pages that are taller are cut off at the bottom
+pub const MAX_CAPTURE_HEIGHT: u32 = 16_384;
+
 /// Alias of usize used for controlling specific views
 /// Only used by advanced to get views, basic simply uses u32
 pub type ViewId = usize;
 
 /// Trait to handle multiple browser engines
 /// Currently only supports cpu renders via pixel_buffer
-/// Passing a View id that does not exist will cause a panic
+/// Passing a View id that does not exist will return [`Error::UnknownView`]
+///
+/// Every view has its own size in physical pixels, set when it is created and changed with [`Engine::resize`]
 pub trait Engine {
     /// Used to do work in the actual browser engine
     fn update(&mut self);
-    /// Has Ultralight perform a new render
-    fn render(&mut self, size: Size<u32>);
+    /// Has the browser engine rerender every view that has changed, each at its own size
+    fn render(&mut self);
     /// Request that the browser engine rerender a specific view that may have been updated
-    fn request_render(&mut self, id: ViewId, size: Size<u32>);
+    fn request_render(&mut self, id: ViewId) -> Result<(), Error>;
+    /// Takes every event that happened in any view since the last call, in the order they happened
+    fn take_events(&mut self) -> Vec<(ViewId, ViewEvent)>;
+    /// Whether any view is loading, animating or waiting to be painted, and so needs frequent updates
+    fn needs_update(&self) -> bool;
+    /// Wakes the webview when the engine has new results outside of [`Engine::update`],
+    /// for example from another thread
+    fn subscription(&self) -> Subscription<()> {
+        Subscription::none()
+    }
     /// Creates new a new (possibly blank) view and returns the ViewId to interact with it
-    fn new_view(&mut self, size: Size<u32>, content: Option<PageType>) -> ViewId;
+    ///
+    /// Views created in the same [`Session`] share their cookies, local storage and cache
+    fn new_view(
+        &mut self,
+        size: Size<u32>,
+        content: Option<PageType>,
+        session: Session,
+    ) -> Result<ViewId, Error>;
     /// Removes desired view
-    fn remove_view(&mut self, id: ViewId);
+    fn remove_view(&mut self, id: ViewId) -> Result<(), Error>;
 
-    /// Focuses webview
-    fn focus(&mut self);
-    /// Unfocuses webview
-    fn unfocus(&self);
-    /// Resizes webview
-    fn resize(&mut self, size: Size<u32>);
+    /// Focuses a specific view, so it shows a caret and accepts keyboard input
+    fn focus(&mut self, id: ViewId) -> Result<(), Error>;
+    /// Unfocuses a specific view
+    fn unfocus(&mut self, id: ViewId) -> Result<(), Error>;
+    /// Whether a specific view is focused
+    fn has_focus(&self, id: ViewId) -> Result<bool, Error>;
+    /// Resizes a specific view, its size is in physical pixels
+    fn resize(&mut self, id: ViewId, size: Size<u32>) -> Result<(), Error>;
+    /// Sets how many physical pixels a specific view draws for each css pixel,
+    /// following the scale factor of the window it is shown in
+    fn set_scale_factor(&mut self, id: ViewId, scale_factor: f32) -> Result<(), Error>;
+    /// Zooms a specific view's page, where 1.0 shows it at its normal size
+    ///
+    /// Zooming is applied on top of the scale factor, and each view keeps its own zoom across navigations
+    fn set_zoom(&mut self, id: ViewId, zoom: f32) -> Result<(), Error>;
 
     /// lets the engine handle keyboard events
-    fn handle_keyboard_event(&mut self, id: ViewId, event: keyboard::Event);
+    fn handle_keyboard_event(&mut self, id: ViewId, event: keyboard::Event) -> Result<(), Error>;
     /// lets the engine handle mouse events
-    fn handle_mouse_event(&mut self, id: ViewId, point: Point, event: mouse::Event);
+    fn handle_mouse_event(
+        &mut self,
+        id: ViewId,
+        point: Point,
+        event: mouse::Event,
+    ) -> Result<(), Error>;
     /// Handles Scrolles on view
-    fn scroll(&mut self, id: ViewId, delta: mouse::ScrollDelta);
+    fn scroll(&mut self, id: ViewId, delta: mouse::ScrollDelta) -> Result<(), Error>;
 
+    /// Sets the files served under [`Assets::URL`] to every view, replacing any set before
+    fn set_assets(&mut self, assets: Assets);
+    /// Routes the requests pages make to `scheme://` urls to the app as [`ViewEvent::Request`]
+    ///
+    /// Only the app's own pages, like those under [`Assets::URL`], should reach it, never websites
+    fn set_scheme(&mut self, scheme: &str);
+    /// Answers a request a specific view made to the app's scheme
+    fn respond(&mut self, id: ViewId, request: RequestId, response: Response) -> Result<(), Error>;
+    /// Answers a dialog a specific view's page opened
+    fn answer_dialog(
+        &mut self,
+        id: ViewId,
+        dialog: DialogId,
+        answer: DialogAnswer,
+    ) -> Result<(), Error>;
+    /// Holds back navigations pages start themselves, like link clicks and redirects,
+    /// reporting them as [`ViewEvent::NavigationRequested`] instead
+    ///
+    /// A held navigation is allowed by going to its url with [`Engine::goto`],
+    /// navigations started through the engine itself are never held
+    fn intercept_navigation(&mut self, intercept: bool);
+    /// Removes data views stored in a session, or in every session when none is given,
+    /// reporting the outcome to [`Engine::take_cleared_data`] once it is done
+    ///
+    /// Views keep showing their pages, which only notice once they read the data again.
+    /// Engines that can not remove some of the data report [`Error::Unsupported`] without removing any
+    fn clear_data(&mut self, session: Option<Session>, data: BrowsingData);
+    /// Takes the outcome of every [`Engine::clear_data`] that finished since the last call,
+    /// in the order they were asked for
+    fn take_cleared_data(&mut self) -> Vec<Result<(), Error>>;
     /// Go to a specific page type
-    fn goto(&mut self, id: ViewId, page_type: PageType);
+    fn goto(&mut self, id: ViewId, page_type: PageType) -> Result<(), Error>;
     /// Refresh specific view
-    fn refresh(&mut self, id: ViewId);
+    fn refresh(&mut self, id: ViewId) -> Result<(), Error>;
     /// Moves forward on view
-    fn go_forward(&mut self, id: ViewId);
+    fn go_forward(&mut self, id: ViewId) -> Result<(), Error>;
     /// Moves back on view
-    fn go_back(&mut self, id: ViewId);
+    fn go_back(&mut self, id: ViewId) -> Result<(), Error>;
+
+    /// Runs JavaScript in a specific view,
+    /// reporting the outcome as a [`ViewEvent::ScriptEvaluated`]
+    ///
+    /// Every script reports exactly one outcome, in the order the scripts were run
+    fn evaluate_script(&mut self, id: ViewId, script: &str) -> Result<(), Error>;
+
+    /// Sends a message to the page in a specific view
+    ///
+    /// It arrives as a `message` event on `window.iced`, with the message as its `data`
+    fn post_message(&mut self, id: ViewId, message: &str) -> Result<(), Error>;
+
+    /// Finds text in a specific view's page and selects the next match, or the previous one when not `forward`,
+    /// reporting the selected match and how many there are as a [`ViewEvent::FindResult`]
+    ///
+    /// Finding the same query again moves on from the selected match, while a new query starts over
+    fn find(
+        &mut self,
+        id: ViewId,
+        query: &str,
+        case_sensitive: bool,
+        forward: bool,
+    ) -> Result<(), Error>;
+    /// Stops finding in a specific view, clearing the selected match
+    fn stop_finding(&mut self, id: ViewId) -> Result<(), Error>;
 
     /// Gets current url from view
-    fn get_url(&self, id: ViewId) -> String;
+    fn get_url(&self, id: ViewId) -> Result<String, Error>;
     /// Gets current title from view
-    fn get_title(&self, id: ViewId) -> String;
+    fn get_title(&self, id: ViewId) -> Result<String, Error>;
+    /// Gets the session a view was created in
+    fn get_session(&self, id: ViewId) -> Result<Session, Error>;
     /// Gets current cursor status from view
-    fn get_cursor(&self, id: ViewId) -> Interaction;
+    fn get_cursor(&self, id: ViewId) -> Result<Interaction, Error>;
+    /// Gets how far a view's page is zoomed, 1.0 until [`Engine::set_zoom`] is called
+    fn get_zoom(&self, id: ViewId) -> Result<f32, Error>;
     /// Gets cpu renderered webview
-    fn get_view(&self, id: ViewId) -> &ImageInfo;
+    fn get_view(&self, id: ViewId) -> Result<&ImageInfo, Error>;
+    /// Renders a view's whole page at the view's width, including what is scrolled out of view,
+    /// reporting it as a [`ViewEvent::FullPageCaptured`]
+    ///
+    /// The view keeps showing the part of the page it was scrolled to
+    fn capture_full_page(&mut self, id: ViewId) -> Result<(), Error>;
 }
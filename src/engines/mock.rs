@@ -0,0 +1,871 @@
+use iced::keyboard;
+use iced::mouse::{self, Interaction};
+use iced::{Point, Size};
+
+use super::{
+    BrowsingData, ConsoleMessage, Dialog, DialogAnswer, DialogId, Engine, FindResult, LoadFailure,
+    NavigationKind, Opener, PageType, PixelFormat, Request, RequestId, Response, ScriptResult,
+    Session, ViewEvent, ViewId,
+};
+use crate::{Assets, Error, ImageInfo};
+
+/// An input event received by a [`Mock`] view, recorded in the order it arrived
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockEvent {
+    /// Received through [`Engine::handle_keyboard_event`]
+    Keyboard(keyboard::Event),
+    /// Received through [`Engine::handle_mouse_event`]
+    Mouse(Point, mouse::Event),
+    /// Received through [`Engine::scroll`]
+    Scroll(mouse::ScrollDelta),
+    /// Received through [`Engine::find`]
+    Find {
+        /// The text searched for
+        query: String,
+        /// Whether letters had to match in case
+        case_sensitive: bool,
+        /// Whether the next match was asked for, rather than the previous one
+        forward: bool,
+    },
+    /// Received through [`Engine::stop_finding`]
+    StopFinding,
+}
+
+/// Holds the in-memory state of a single mock view
+pub struct View {
+    id: ViewId,
+    history: Vec<PageType>,
+    history_index: usize,
+    events: Vec<MockEvent>,
+    view_events: Vec<ViewEvent>,
+    scripts: Vec<String>,
+    script_result: ScriptResult,
+    find_result: (usize, usize),
+    messages: Vec<String>,
+    next_request: RequestId,
+    responses: Vec<(RequestId, Response)>,
+    next_dialog: DialogId,
+    dialog_answers: Vec<(DialogId, DialogAnswer)>,
+    cursor: Interaction,
+    size: Size<u32>,
+    scale_factor: f32,
+    zoom: f32,
+    focused: bool,
+    last_frame: ImageInfo,
+    frames: usize,
+    assets: Assets,
+    session: Session,
+}
+
+impl View {
+    fn current_page(&self) -> Option<&PageType> {
+        self.history.get(self.history_index)
+    }
+
+    fn navigate(&mut self, page_type: PageType) {
+        if !self.history.is_empty() {
+            self.history.truncate(self.history_index + 1);
+        }
+        self.history.push(page_type);
+        self.history_index = self.history.len() - 1;
+        self.view_events.push(ViewEvent::HistoryChanged);
+        self.load();
+    }
+
+    fn go_to_history(&mut self, index: usize) {
+        if index != self.history_index && index < self.history.len() {
+            self.history_index = index;
+            self.view_events.push(ViewEvent::HistoryChanged);
+            self.load();
+        }
+    }
+
+    /// Pages load instantly, so the whole lifecycle is reported at once
+    fn load(&mut self) {
+        let url = self.url();
+        if let Some(path) = Assets::path(&url) {
+            if self.assets.get(path).is_none() {
+                self.view_events.extend([
+                    ViewEvent::LoadStarted(url.clone()),
+                    ViewEvent::LoadFailed(LoadFailure {
+                        url: url.clone(),
+                        code: 404,
+                        description: format!("no asset was set for {path}"),
+                    }),
+                ]);
+                return;
+            }
+        }
+        self.view_events.extend([
+            ViewEvent::LoadStarted(url.clone()),
+            ViewEvent::DomReady(url.clone()),
+            ViewEvent::LoadFinished(url),
+        ]);
+    }
+
+    fn url(&self) -> String {
+        match self.current_page() {
+            Some(PageType::Url(url)) => url.clone(),
+            Some(PageType::Html(_)) | None => String::new(),
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.current_page() {
+            Some(PageType::Url(url)) => url.clone(),
+            Some(PageType::Html(html)) => html
+                .split_once("<title>")
+                .and_then(|(_, rest)| rest.split_once("</title>"))
+                .map(|(title, _)| title.trim().to_string())
+                .unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    /// Paints the whole frame a single colour derived from the current page,
+    /// so the same page at the same size always produces the same frame
+    fn paint(&mut self) {
+        let size = self.size;
+        let seed = match self.current_page() {
+            Some(PageType::Url(url)) => fnv1a(url.as_bytes()),
+            Some(PageType::Html(html)) => fnv1a(html.as_bytes()),
+            None => fnv1a(&[]),
+        };
+        let [r, g, b, ..] = seed.to_le_bytes();
+        let pixels = [r, g, b, 255].repeat(size.width as usize * size.height as usize);
+        self.last_frame = ImageInfo::new(pixels, PixelFormat::Rgba, size.width, size.height);
+        self.frames += 1;
+    }
+}
+
+/// A headless, in-memory implementation of Engine
+///
+/// It never touches the network or a native SDK, which makes it suitable for testing
+/// applications that embed a webview. Each view keeps its url, title, back/forward
+/// history and every input event it receives, and renders deterministic frames.
+/// Pages load instantly, reporting their whole load lifecycle at once, and
+/// [`Mock::fail_load`] stands in for a page that failed to load.
+/// Urls under [`Assets::URL`] only load if an asset was set for them.
+///
+/// ```rust
+/// use iced_webview::{engines::mock::MockEvent, Action, Engine, Mock, PageType, Session, WebView};
+/// use iced::keyboard::{self, Modifiers};
+///
+/// #[derive(Clone)]
+/// enum Message {}
+///
+/// let mut webview: WebView<Mock, Message> = WebView::new();
+/// let _ = webview.update(Action::CreateView(
+///     PageType::Url("https://example.com".to_string()),
+///     Session::Default,
+/// ));
+/// let _ = webview.update(Action::ChangeView(0));
+/// let _ = webview.update(Action::SendKeyboardEvent(keyboard::Event::ModifiersChanged(
+///     Modifiers::SHIFT,
+/// )));
+///
+/// let engine = webview.engine();
+/// let id = engine.view_ids()[0];
+/// assert_eq!(engine.get_url(id).unwrap(), "https://example.com");
+/// assert_eq!(
+///     engine.events(id).unwrap(),
+///     [MockEvent::Keyboard(keyboard::Event::ModifiersChanged(Modifiers::SHIFT))]
+/// );
+/// ```
+#[derive(Default)]
+pub struct Mock {
+    views: Vec<View>,
+    next_id: ViewId,
+    assets: Assets,
+    intercept_navigation: bool,
+    cleared_data: Vec<(Option<Session>, BrowsingData)>,
+    // outcomes of clear_data waiting to be taken, and the error the next one fails with
+    clear_outcomes: Vec<Result<(), Error>>,
+    clear_error: Option<Error>,
+}
+
+impl Mock {
+    /// Creates a new mock engine with no views
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The ids of all open views, in the order they were created
+    pub fn view_ids(&self) -> Vec<ViewId> {
+        self.views.iter().map(|view| view.id).collect()
+    }
+
+    /// Every input event the view has received so far
+    pub fn events(&self, id: ViewId) -> Result<&[MockEvent], Error> {
+        Ok(&self.get_view(id)?.events)
+    }
+
+    /// How many frames the view has painted so far
+    pub fn frames(&self, id: ViewId) -> Result<usize, Error> {
+        Ok(self.get_view(id)?.frames)
+    }
+
+    /// Forgets the input events the view has received so far
+    pub fn clear_events(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.events.clear();
+        Ok(())
+    }
+
+    /// The full back/forward history of the view, and the index of the current page in it
+    pub fn history(&self, id: ViewId) -> Result<(&[PageType], usize), Error> {
+        let view = self.get_view(id)?;
+        Ok((&view.history, view.history_index))
+    }
+
+    /// The size the view was created or last resized at
+    pub fn view_size(&self, id: ViewId) -> Result<Size<u32>, Error> {
+        Ok(self.get_view(id)?.size)
+    }
+
+    /// The scale factor the view was last given, 1.0 until then
+    pub fn scale_factor(&self, id: ViewId) -> Result<f32, Error> {
+        Ok(self.get_view(id)?.scale_factor)
+    }
+
+    /// Every script the view has been asked to evaluate so far
+    pub fn scripts(&self, id: ViewId) -> Result<&[String], Error> {
+        Ok(&self.get_view(id)?.scripts)
+    }
+
+    /// Sets the outcome every script evaluated in the view reports, `Ok("undefined")` by default
+    pub fn set_script_result(&mut self, id: ViewId, result: ScriptResult) -> Result<(), Error> {
+        self.get_view_mut(id)?.script_result = result;
+        Ok(())
+    }
+
+    /// Sets the selected match and the number of matches every find in the view reports, `(0, 0)` by default
+    pub fn set_find_result(
+        &mut self,
+        id: ViewId,
+        active_match: usize,
+        total_matches: usize,
+    ) -> Result<(), Error> {
+        self.get_view_mut(id)?.find_result = (active_match, total_matches);
+        Ok(())
+    }
+
+    /// Every message posted to the view's page so far
+    pub fn messages(&self, id: ViewId) -> Result<&[String], Error> {
+        Ok(&self.get_view(id)?.messages)
+    }
+
+    /// Reports a message, as if the page had called `window.iced.postMessage`
+    pub fn post_message_from_page(&mut self, id: ViewId, message: &str) -> Result<(), Error> {
+        self.get_view_mut(id)?
+            .view_events
+            .push(ViewEvent::Message(message.to_string()));
+        Ok(())
+    }
+
+    /// Navigates as if the page had, like by clicking a link,
+    /// reporting it instead if navigations are intercepted
+    pub fn navigate_from_page(
+        &mut self,
+        id: ViewId,
+        url: &str,
+        kind: NavigationKind,
+    ) -> Result<(), Error> {
+        let intercept = self.intercept_navigation;
+        let view = self.get_view_mut(id)?;
+        match intercept {
+            true => view
+                .view_events
+                .push(ViewEvent::NavigationRequested(url.to_string(), kind)),
+            false => view.navigate(PageType::Url(url.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Asks for a new window, as if the page had clicked a `target="_blank"` link
+    /// or called `window.open`
+    pub fn open_window_from_page(
+        &mut self,
+        id: ViewId,
+        url: &str,
+        opener: Opener,
+    ) -> Result<(), Error> {
+        self.get_view_mut(id)?
+            .view_events
+            .push(ViewEvent::NewWindowRequested(url.to_string(), opener));
+        Ok(())
+    }
+
+    /// Reports a request, as if the page had fetched a url with the app's scheme
+    pub fn request_from_page(&mut self, id: ViewId, request: Request) -> Result<RequestId, Error> {
+        let view = self.get_view_mut(id)?;
+        let request_id = view.next_request;
+        view.next_request += 1;
+        view.view_events
+            .push(ViewEvent::Request(request_id, request));
+        Ok(request_id)
+    }
+
+    /// Every response the view's requests were answered with so far
+    pub fn responses(&self, id: ViewId) -> Result<&[(RequestId, Response)], Error> {
+        Ok(&self.get_view(id)?.responses)
+    }
+
+    /// Opens a dialog, as if the page had called `alert` or one of `window.iced`'s dialogs
+    pub fn dialog_from_page(&mut self, id: ViewId, dialog: Dialog) -> Result<DialogId, Error> {
+        let view = self.get_view_mut(id)?;
+        let dialog_id = view.next_dialog;
+        view.next_dialog += 1;
+        view.view_events
+            .push(ViewEvent::DialogRequested(dialog_id, dialog));
+        Ok(dialog_id)
+    }
+
+    /// Every answer the view's dialogs were given so far
+    pub fn dialog_answers(&self, id: ViewId) -> Result<&[(DialogId, DialogAnswer)], Error> {
+        Ok(&self.get_view(id)?.dialog_answers)
+    }
+
+    /// Every call to [`Engine::clear_data`] so far, in the order they were made
+    pub fn cleared_data(&self) -> &[(Option<Session>, BrowsingData)] {
+        &self.cleared_data
+    }
+
+    /// Makes the next call to [`Engine::clear_data`] report an error, like an engine that can not clear the data
+    pub fn fail_clearing_data(&mut self, error: Error) {
+        self.clear_error = Some(error);
+    }
+
+    /// Reports a console message, as if the page had logged it
+    pub fn log_from_page(&mut self, id: ViewId, message: ConsoleMessage) -> Result<(), Error> {
+        self.get_view_mut(id)?
+            .view_events
+            .push(ViewEvent::ConsoleMessage(message));
+        Ok(())
+    }
+
+    /// Reports that the current page failed to load, as if the network had failed
+    pub fn fail_load(&mut self, id: ViewId, code: i32, description: &str) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        let url = view.url();
+        view.view_events.push(ViewEvent::LoadFailed(LoadFailure {
+            url,
+            code,
+            description: description.to_string(),
+        }));
+        Ok(())
+    }
+
+    /// Sets the cursor the view reports, as if the page had changed it
+    pub fn set_cursor(&mut self, id: ViewId, cursor: Interaction) -> Result<(), Error> {
+        self.get_view_mut(id)?.cursor = cursor;
+        Ok(())
+    }
+
+    fn get_view(&self, id: ViewId) -> Result<&View, Error> {
+        self.views
+            .iter()
+            .find(|view| view.id == id)
+            .ok_or(Error::UnknownView(id))
+    }
+
+    fn get_view_mut(&mut self, id: ViewId) -> Result<&mut View, Error> {
+        self.views
+            .iter_mut()
+            .find(|view| view.id == id)
+            .ok_or(Error::UnknownView(id))
+    }
+}
+
+impl Engine for Mock {
+    fn update(&mut self) {}
+
+    fn render(&mut self) {
+        self.views.iter_mut().for_each(View::paint);
+    }
+
+    fn take_events(&mut self) -> Vec<(ViewId, ViewEvent)> {
+        self.views
+            .iter_mut()
+            .flat_map(|view| view.view_events.drain(..).map(|event| (view.id, event)))
+            .collect()
+    }
+
+    fn needs_update(&self) -> bool {
+        // frames are painted straight away, so there is never anything pending
+        false
+    }
+
+    fn request_render(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.paint();
+        Ok(())
+    }
+
+    fn new_view(
+        &mut self,
+        size: Size<u32>,
+        content: Option<PageType>,
+        session: Session,
+    ) -> Result<ViewId, Error> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut view = View {
+            id,
+            history: Vec::new(),
+            history_index: 0,
+            events: Vec::new(),
+            view_events: Vec::new(),
+            scripts: Vec::new(),
+            script_result: Ok("undefined".to_string()),
+            find_result: (0, 0),
+            messages: Vec::new(),
+            next_request: 0,
+            responses: Vec::new(),
+            next_dialog: 0,
+            dialog_answers: Vec::new(),
+            cursor: Interaction::Idle,
+            size,
+            scale_factor: 1.0,
+            zoom: 1.0,
+            focused: false,
+            last_frame: ImageInfo::blank(size.width, size.height),
+            frames: 0,
+            assets: self.assets.clone(),
+            session,
+        };
+        if let Some(page_type) = content {
+            view.navigate(page_type);
+        }
+        view.paint();
+        self.views.push(view);
+        Ok(id)
+    }
+
+    fn remove_view(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view(id)?;
+        self.views.retain(|view| view.id != id);
+        Ok(())
+    }
+
+    fn focus(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.focused = true;
+        Ok(())
+    }
+
+    fn unfocus(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.focused = false;
+        Ok(())
+    }
+
+    fn has_focus(&self, id: ViewId) -> Result<bool, Error> {
+        Ok(self.get_view(id)?.focused)
+    }
+
+    fn resize(&mut self, id: ViewId, size: Size<u32>) -> Result<(), Error> {
+        self.get_view_mut(id)?.size = size;
+        Ok(())
+    }
+
+    fn set_scale_factor(&mut self, id: ViewId, scale_factor: f32) -> Result<(), Error> {
+        self.get_view_mut(id)?.scale_factor = scale_factor;
+        Ok(())
+    }
+
+    fn set_zoom(&mut self, id: ViewId, zoom: f32) -> Result<(), Error> {
+        self.get_view_mut(id)?.zoom = zoom;
+        Ok(())
+    }
+
+    fn handle_keyboard_event(&mut self, id: ViewId, event: keyboard::Event) -> Result<(), Error> {
+        self.get_view_mut(id)?
+            .events
+            .push(MockEvent::Keyboard(event));
+        Ok(())
+    }
+
+    fn handle_mouse_event(
+        &mut self,
+        id: ViewId,
+        point: Point,
+        event: mouse::Event,
+    ) -> Result<(), Error> {
+        self.get_view_mut(id)?
+            .events
+            .push(MockEvent::Mouse(point, event));
+        Ok(())
+    }
+
+    fn scroll(&mut self, id: ViewId, delta: mouse::ScrollDelta) -> Result<(), Error> {
+        self.get_view_mut(id)?.events.push(MockEvent::Scroll(delta));
+        Ok(())
+    }
+
+    fn set_assets(&mut self, assets: Assets) {
+        for view in self.views.iter_mut() {
+            view.assets = assets.clone();
+        }
+        self.assets = assets;
+    }
+
+    fn set_scheme(&mut self, _scheme: &str) {
+        // requests are only made through Mock::request_from_page
+    }
+
+    fn respond(&mut self, id: ViewId, request: RequestId, response: Response) -> Result<(), Error> {
+        self.get_view_mut(id)?.responses.push((request, response));
+        Ok(())
+    }
+
+    fn answer_dialog(
+        &mut self,
+        id: ViewId,
+        dialog: DialogId,
+        answer: DialogAnswer,
+    ) -> Result<(), Error> {
+        self.get_view_mut(id)?.dialog_answers.push((dialog, answer));
+        Ok(())
+    }
+
+    fn intercept_navigation(&mut self, intercept: bool) {
+        self.intercept_navigation = intercept;
+    }
+
+    fn clear_data(&mut self, session: Option<Session>, data: BrowsingData) {
+        self.cleared_data.push((session, data));
+        let outcome = self.clear_error.take().map_or(Ok(()), Err);
+        self.clear_outcomes.push(outcome);
+    }
+
+    fn take_cleared_data(&mut self) -> Vec<Result<(), Error>> {
+        std::mem::take(&mut self.clear_outcomes)
+    }
+
+    fn goto(&mut self, id: ViewId, page_type: PageType) -> Result<(), Error> {
+        self.get_view_mut(id)?.navigate(page_type);
+        Ok(())
+    }
+
+    fn refresh(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.load();
+        Ok(())
+    }
+
+    fn go_forward(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.go_to_history(view.history_index + 1);
+        Ok(())
+    }
+
+    fn go_back(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.go_to_history(view.history_index.saturating_sub(1));
+        Ok(())
+    }
+
+    fn evaluate_script(&mut self, id: ViewId, script: &str) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.scripts.push(script.to_string());
+        view.view_events
+            .push(ViewEvent::ScriptEvaluated(view.script_result.clone()));
+        Ok(())
+    }
+
+    fn post_message(&mut self, id: ViewId, message: &str) -> Result<(), Error> {
+        self.get_view_mut(id)?.messages.push(message.to_string());
+        Ok(())
+    }
+
+    fn find(
+        &mut self,
+        id: ViewId,
+        query: &str,
+        case_sensitive: bool,
+        forward: bool,
+    ) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.events.push(MockEvent::Find {
+            query: query.to_string(),
+            case_sensitive,
+            forward,
+        });
+        let (active_match, total_matches) = view.find_result;
+        view.view_events.push(ViewEvent::FindResult(FindResult {
+            query: query.to_string(),
+            case_sensitive,
+            active_match,
+            total_matches,
+        }));
+        Ok(())
+    }
+
+    fn stop_finding(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.events.push(MockEvent::StopFinding);
+        Ok(())
+    }
+
+    fn get_url(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self.get_view(id)?.url())
+    }
+
+    fn get_title(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self.get_view(id)?.title())
+    }
+
+    fn get_session(&self, id: ViewId) -> Result<Session, Error> {
+        Ok(self.get_view(id)?.session.clone())
+    }
+
+    fn get_cursor(&self, id: ViewId) -> Result<Interaction, Error> {
+        Ok(self.get_view(id)?.cursor)
+    }
+
+    fn get_zoom(&self, id: ViewId) -> Result<f32, Error> {
+        Ok(self.get_view(id)?.zoom)
+    }
+
+    fn get_view(&self, id: ViewId) -> Result<&ImageInfo, Error> {
+        Ok(&self.get_view(id)?.last_frame)
+    }
+
+    /// Pages are never taller than their view, so the current frame is reported
+    fn capture_full_page(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        let frame = view.last_frame.clone();
+        view.view_events.push(ViewEvent::FullPageCaptured(frame));
+        Ok(())
+    }
+}
+
+// A tiny, stable hash so frames don't change between Rust releases
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Size<u32> = Size::new(40, 30);
+
+    fn url(page: &str) -> PageType {
+        PageType::Url(format!("https://example.com/{page}"))
+    }
+
+    fn events(engine: &mut Mock) -> Vec<ViewEvent> {
+        engine
+            .take_events()
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    #[test]
+    fn pages_load_at_once() {
+        let mut engine = Mock::new();
+        let id = engine
+            .new_view(SIZE, Some(url("a")), Session::Default)
+            .unwrap();
+
+        let loaded = "https://example.com/a".to_string();
+        assert_eq!(
+            events(&mut engine),
+            [
+                ViewEvent::HistoryChanged,
+                ViewEvent::LoadStarted(loaded.clone()),
+                ViewEvent::DomReady(loaded.clone()),
+                ViewEvent::LoadFinished(loaded.clone()),
+            ]
+        );
+        assert_eq!(engine.get_url(id).unwrap(), loaded);
+        assert_eq!(engine.get_title(id).unwrap(), loaded);
+
+        let html = PageType::Html("<title> Help </title>".to_string());
+        engine.goto(id, html).unwrap();
+        assert_eq!(engine.get_url(id).unwrap(), "");
+        assert_eq!(engine.get_title(id).unwrap(), "Help");
+    }
+
+    #[test]
+    fn history_is_kept_like_a_browser() {
+        let mut engine = Mock::new();
+        let id = engine
+            .new_view(SIZE, Some(url("a")), Session::Default)
+            .unwrap();
+        engine.goto(id, url("b")).unwrap();
+        engine.goto(id, url("c")).unwrap();
+
+        engine.go_back(id).unwrap();
+        engine.go_back(id).unwrap();
+        assert_eq!(engine.history(id).unwrap().1, 0);
+        events(&mut engine);
+
+        // there is nothing further back, or further forward
+        engine.go_back(id).unwrap();
+        assert_eq!(events(&mut engine), []);
+        engine.go_forward(id).unwrap();
+        engine.go_forward(id).unwrap();
+        engine.go_forward(id).unwrap();
+        assert_eq!(engine.get_url(id).unwrap(), "https://example.com/c");
+
+        // navigating from the middle of the history drops what was ahead of it
+        engine.go_back(id).unwrap();
+        engine.goto(id, url("d")).unwrap();
+        assert_eq!(
+            engine.history(id).unwrap(),
+            (&[url("a"), url("b"), url("d")][..], 2)
+        );
+    }
+
+    #[test]
+    fn missing_assets_fail_to_load() {
+        let mut engine = Mock::new();
+        let page = PageType::Url(Assets::url("index.html"));
+        let id = engine
+            .new_view(SIZE, Some(page.clone()), Session::Default)
+            .unwrap();
+        assert!(matches!(
+            events(&mut engine)[..],
+            [
+                ViewEvent::HistoryChanged,
+                ViewEvent::LoadStarted(_),
+                ViewEvent::LoadFailed(LoadFailure { code: 404, .. })
+            ]
+        ));
+
+        engine.set_assets(Assets::new().with("index.html", b"<p>Hi</p>".to_vec()));
+        engine.refresh(id).unwrap();
+        assert!(events(&mut engine).contains(&ViewEvent::LoadFinished(Assets::url("index.html"))));
+    }
+
+    #[test]
+    fn navigations_from_pages_can_be_intercepted() {
+        let mut engine = Mock::new();
+        let id = engine
+            .new_view(SIZE, Some(url("a")), Session::Default)
+            .unwrap();
+        events(&mut engine);
+
+        engine.intercept_navigation(true);
+        let link = "https://example.com/b";
+        engine
+            .navigate_from_page(id, link, NavigationKind::LinkClick)
+            .unwrap();
+        assert_eq!(
+            events(&mut engine),
+            [ViewEvent::NavigationRequested(
+                link.to_string(),
+                NavigationKind::LinkClick
+            )]
+        );
+        assert_eq!(engine.get_url(id).unwrap(), "https://example.com/a");
+
+        engine.intercept_navigation(false);
+        engine
+            .navigate_from_page(id, link, NavigationKind::LinkClick)
+            .unwrap();
+        assert_eq!(engine.get_url(id).unwrap(), link);
+    }
+
+    #[test]
+    fn input_events_are_recorded_in_order() {
+        let mut engine = Mock::new();
+        let id = engine.new_view(SIZE, None, Session::Default).unwrap();
+
+        let key = keyboard::Event::ModifiersChanged(keyboard::Modifiers::CTRL);
+        let click = mouse::Event::ButtonPressed(mouse::Button::Left);
+        let delta = mouse::ScrollDelta::Lines { x: 0.0, y: 1.0 };
+        engine.handle_keyboard_event(id, key.clone()).unwrap();
+        engine
+            .handle_mouse_event(id, Point::new(1.0, 2.0), click)
+            .unwrap();
+        engine.scroll(id, delta).unwrap();
+        engine.set_find_result(id, 1, 3).unwrap();
+        engine.find(id, "iced", true, false).unwrap();
+        engine.stop_finding(id).unwrap();
+
+        assert_eq!(
+            engine.events(id).unwrap(),
+            [
+                MockEvent::Keyboard(key),
+                MockEvent::Mouse(Point::new(1.0, 2.0), click),
+                MockEvent::Scroll(delta),
+                MockEvent::Find {
+                    query: "iced".to_string(),
+                    case_sensitive: true,
+                    forward: false,
+                },
+                MockEvent::StopFinding,
+            ]
+        );
+        assert_eq!(
+            events(&mut engine),
+            [ViewEvent::FindResult(FindResult {
+                query: "iced".to_string(),
+                case_sensitive: true,
+                active_match: 1,
+                total_matches: 3,
+            })]
+        );
+
+        engine.clear_events(id).unwrap();
+        assert_eq!(engine.events(id).unwrap(), []);
+    }
+
+    #[test]
+    fn frames_follow_the_page_and_size() {
+        let mut engine = Mock::new();
+        let a = engine
+            .new_view(SIZE, Some(url("a")), Session::Default)
+            .unwrap();
+        let b = engine
+            .new_view(SIZE, Some(url("a")), Session::Default)
+            .unwrap();
+        assert_eq!(Engine::get_view(&engine, a), Engine::get_view(&engine, b));
+        // a frame is uploaded to the gpu once, however often it is shown
+        let frame = Engine::get_view(&engine, a).unwrap();
+        assert_eq!(frame.to_handle().id(), frame.clone().to_handle().id());
+
+        engine.goto(b, url("b")).unwrap();
+        engine.resize(b, Size::new(10, 20)).unwrap();
+        engine.request_render(b).unwrap();
+        let frame = Engine::get_view(&engine, b).unwrap();
+        assert_eq!((frame.width(), frame.height()), (10, 20));
+        assert_ne!(Engine::get_view(&engine, a), Engine::get_view(&engine, b));
+        assert_eq!(engine.frames(b).unwrap(), 2);
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let mut engine = Mock::new();
+        let id = engine.new_view(SIZE, None, Session::Default).unwrap();
+        engine.remove_view(id).unwrap();
+        assert_eq!(engine.remove_view(id), Err(Error::UnknownView(id)));
+        assert_eq!(engine.get_url(id), Err(Error::UnknownView(id)));
+        assert_eq!(engine.goto(id, url("a")), Err(Error::UnknownView(id)));
+
+        engine.fail_clearing_data(Error::Unsupported("cookies".to_string()));
+        engine.clear_data(None, BrowsingData::ALL);
+        engine.clear_data(None, BrowsingData::ALL);
+        assert_eq!(
+            engine.take_cleared_data(),
+            [Err(Error::Unsupported("cookies".to_string())), Ok(())]
+        );
+        assert_eq!(engine.cleared_data().len(), 2);
+
+        let id = engine
+            .new_view(SIZE, Some(url("a")), Session::Default)
+            .unwrap();
+        events(&mut engine);
+        engine.fail_load(id, -2, "offline").unwrap();
+        assert_eq!(
+            events(&mut engine),
+            [ViewEvent::LoadFailed(LoadFailure {
+                url: "https://example.com/a".to_string(),
+                code: -2,
+                description: "offline".to_string(),
+            })]
+        );
+    }
+}
@@ -0,0 +1,1114 @@
+use cosmic_text::{FontSystem, SwashCache};
+use iced::keyboard::{self, key::Named, Key};
+use iced::mouse::{self, Interaction, ScrollDelta};
+use iced::{Point, Rectangle, Size};
+use rand::Rng;
+use scraper::{Html, Selector};
+use std::fs;
+use std::io;
+use url::Url;
+
+mod layout;
+mod style;
+
+use layout::{Canvas, FieldKind, FieldValue, Focusable, Matches, Page};
+use style::Stylesheet;
+
+use super::{
+    BrowsingData, DialogAnswer, DialogId, Engine, FindResult, LoadFailure, NavigationKind, Opener,
+    PageType, PixelFormat, RequestId, Response, Session, ViewEvent, ViewId, MAX_CAPTURE_HEIGHT,
+};
+use crate::{Assets, Error, ImageInfo};
+
+// How far one line of a mouse wheel or an arrow key scrolls
+const SCROLL_LINE: f32 = 40.0;
+
+/// The fonts shared by every view
+struct Fonts {
+    system: FontSystem,
+    cache: SwashCache,
+}
+
+/// A parsed page and the stylesheets it references
+struct Document {
+    html: Html,
+    stylesheet: Stylesheet,
+    url: Option<Url>,
+    title: String,
+}
+
+impl Document {
+    /// Failures use the OS error code when a file could not be read, and 0 otherwise
+    fn load(page_type: &PageType, assets: &Assets) -> Result<Self, LoadFailure> {
+        match page_type {
+            PageType::Html(html) => Ok(Self::parse(html, None, assets)),
+            PageType::Url(url) => {
+                let failure = |code, description: String| LoadFailure {
+                    url: url.clone(),
+                    code,
+                    description,
+                };
+                let url = Url::parse(url).map_err(|err| failure(0, err.to_string()))?;
+                let html = match url.scheme() {
+                    "file" => read_file(&url, assets).map_err(|err| {
+                        failure(err.raw_os_error().unwrap_or_default(), err.to_string())
+                    })?,
+                    "about" if url.path() == "blank" => String::new(),
+                    scheme => {
+                        return Err(failure(
+                            0,
+                            format!(
+                            "the {scheme} scheme is not supported, only local content can be shown"
+                        ),
+                        ))
+                    }
+                };
+                Ok(Self::parse(&html, Some(url), assets))
+            }
+        }
+    }
+
+    fn parse(source: &str, url: Option<Url>, assets: &Assets) -> Self {
+        let html = Html::parse_document(source);
+
+        let title = Selector::parse("title")
+            .ok()
+            .and_then(|selector| html.select(&selector).next())
+            .map(|title| title.text().collect::<String>().trim().to_string())
+            .unwrap_or_default();
+
+        // inline <style> blocks and local <link rel="stylesheet"> files, in document order
+        let mut css = String::new();
+        if let Ok(selector) = Selector::parse("style, link[rel~=stylesheet][href]") {
+            for element in html.select(&selector) {
+                match element.value().attr("href") {
+                    Some(href) => {
+                        let stylesheet = url
+                            .as_ref()
+                            .and_then(|url| url.join(href).ok())
+                            .filter(|url| url.scheme() == "file")
+                            .and_then(|url| read_file(&url, assets).ok());
+                        css.extend(stylesheet);
+                    }
+                    None => css.extend(element.text()),
+                }
+                css.push('\n');
+            }
+        }
+
+        Self {
+            html,
+            stylesheet: Stylesheet::parse(&css),
+            url,
+            title,
+        }
+    }
+}
+
+/// Reads a file url, from the assets if it is under [`Assets::URL`]
+fn read_file(url: &Url, assets: &Assets) -> io::Result<String> {
+    if let Some(path) = Assets::path(url.as_str()) {
+        return assets
+            .get(path)
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no asset was set for {path}"),
+                )
+            });
+    }
+    let path = url
+        .to_file_path()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "not a valid file path"))?;
+    fs::read_to_string(path)
+}
+
+/// Holds a laid out page and its scroll, focus and form state
+pub struct View {
+    id: ViewId,
+    history: Vec<PageType>,
+    history_index: usize,
+    document: Document,
+    values: Vec<FieldValue>,
+    page: Page,
+    size: Size<u32>,
+    // how many pixels a css pixel covers, before zooming
+    scale_factor: f32,
+    zoom: f32,
+    scroll_y: f32,
+    // fragment to scroll to once the page has been laid out
+    pending_anchor: Option<String>,
+    cursor: Interaction,
+    focus: Option<Focusable>,
+    // whether the view itself is focused, which shows the focus ring
+    has_focus: bool,
+    needs_layout: bool,
+    needs_paint: bool,
+    last_frame: ImageInfo,
+    events: Vec<ViewEvent>,
+    assets: Assets,
+    intercept_navigation: bool,
+    // only recorded, since there are no cookies or storage to keep apart
+    session: Session,
+    finding: Option<Finding>,
+}
+
+/// What a view was last asked to find, and where it was found
+struct Finding {
+    query: String,
+    case_sensitive: bool,
+    matches: Matches,
+}
+
+impl View {
+    fn navigate(&mut self, page_type: PageType) {
+        if let Some(document) = self.load(&page_type) {
+            if !self.history.is_empty() {
+                self.history.truncate(self.history_index + 1);
+            }
+            self.history.push(page_type);
+            self.history_index = self.history.len() - 1;
+            self.events.push(ViewEvent::HistoryChanged);
+            self.show(document);
+        }
+    }
+
+    fn go_back(&mut self) {
+        if let Some(index) = self.history_index.checked_sub(1) {
+            self.go_to_history(index);
+        }
+    }
+
+    fn go_forward(&mut self) {
+        self.go_to_history(self.history_index + 1);
+    }
+
+    fn go_to_history(&mut self, index: usize) {
+        if let Some(page_type) = self.history.get(index).cloned() {
+            if let Some(document) = self.load(&page_type) {
+                if self.history_index != index {
+                    self.history_index = index;
+                    self.events.push(ViewEvent::HistoryChanged);
+                }
+                self.show(document);
+            }
+        }
+    }
+
+    /// Loads a page, reporting its whole lifecycle at once since loading is synchronous
+    ///
+    /// A page that fails to load leaves the current one in place
+    fn load(&mut self, page_type: &PageType) -> Option<Document> {
+        let url = match page_type {
+            PageType::Url(url) => url.clone(),
+            PageType::Html(_) => String::new(),
+        };
+        self.events.push(ViewEvent::LoadStarted(url.clone()));
+        match Document::load(page_type, &self.assets) {
+            Ok(document) => {
+                self.events.extend([
+                    ViewEvent::DomReady(url.clone()),
+                    ViewEvent::LoadFinished(url),
+                ]);
+                Some(document)
+            }
+            Err(failure) => {
+                self.events.push(ViewEvent::LoadFailed(failure));
+                None
+            }
+        }
+    }
+
+    fn show(&mut self, document: Document) {
+        self.pending_anchor = document
+            .url
+            .as_ref()
+            .and_then(|url| url.fragment())
+            .map(str::to_string);
+        self.document = document;
+        self.values.clear();
+        self.scroll_y = 0.0;
+        self.focus = None;
+        self.cursor = Interaction::Idle;
+        self.finding = None;
+        self.needs_layout = true;
+    }
+
+    /// Follows a link, or asks the app to open it in a new view if it targets `_blank`
+    fn activate_link(&mut self, index: usize) -> Result<(), Error> {
+        let link = &self.page.links[index];
+        let href = link.href.clone();
+        if !link.new_window || href.starts_with("javascript:") {
+            return self.follow(&href);
+        }
+        let target = self.resolve(&href)?;
+        self.events.push(ViewEvent::NewWindowRequested(
+            target.to_string(),
+            Opener {
+                url: self
+                    .document
+                    .url
+                    .as_ref()
+                    .map(Url::to_string)
+                    .unwrap_or_default(),
+                is_popup: false,
+                rect: None,
+            },
+        ));
+        Ok(())
+    }
+
+    /// Resolves an href relative to the current page
+    fn resolve(&self, href: &str) -> Result<Url, Error> {
+        match &self.document.url {
+            Some(base) => base.join(href),
+            None => Url::parse(href),
+        }
+        .map_err(|err| Error::LoadFailed(format!("{href}: {err}")))
+    }
+
+    /// Follows a link's href, relative to the current page
+    fn follow(&mut self, href: &str) -> Result<(), Error> {
+        if let Some(anchor) = href.strip_prefix('#') {
+            self.scroll_to_anchor(anchor);
+            return Ok(());
+        }
+        if href.starts_with("javascript:") {
+            return Ok(());
+        }
+
+        let target = self.resolve(href)?;
+
+        let same_document = self.document.url.as_ref().is_some_and(|current| {
+            let mut current = current.clone();
+            let mut target = target.clone();
+            current.set_fragment(None);
+            target.set_fragment(None);
+            current == target
+        });
+        match (same_document, target.fragment()) {
+            (true, Some(anchor)) => {
+                self.scroll_to_anchor(anchor);
+                Ok(())
+            }
+            _ if self.intercept_navigation => {
+                self.events.push(ViewEvent::NavigationRequested(
+                    target.to_string(),
+                    NavigationKind::LinkClick,
+                ));
+                Ok(())
+            }
+            _ => {
+                self.navigate(PageType::Url(target.to_string()));
+                Ok(())
+            }
+        }
+    }
+
+    fn scroll_to_anchor(&mut self, anchor: &str) {
+        if let Some(y) = self.page.anchor(anchor) {
+            self.scroll_to(y);
+        }
+    }
+
+    fn scroll_to(&mut self, y: f32) {
+        let max = (self.page.height - self.size.height as f32).max(0.0);
+        self.scroll_y = y.clamp(0.0, max);
+        self.needs_paint = true;
+    }
+
+    fn resize(&mut self, size: Size<u32>) {
+        if self.size.width != size.width {
+            self.needs_layout = true;
+        }
+        if self.size != size {
+            self.size = size;
+            self.needs_paint = true;
+        }
+    }
+
+    /// How many pixels a css pixel covers once zoomed
+    fn scale(&self) -> f32 {
+        self.scale_factor * self.zoom
+    }
+
+    fn set_scale(&mut self, scale_factor: f32, zoom: f32) {
+        if self.scale_factor != scale_factor || self.zoom != zoom {
+            // keep the same part of the page in view
+            self.scroll_y *= scale_factor * zoom / self.scale();
+            self.scale_factor = scale_factor;
+            self.zoom = zoom;
+            self.needs_layout = true;
+            self.needs_paint = true;
+        }
+    }
+
+    fn layout(&mut self, fonts: &mut Fonts) {
+        if !self.needs_layout {
+            return;
+        }
+        self.page = Page::layout(
+            &self.document.html,
+            &self.document.stylesheet,
+            self.size.width as f32,
+            self.scale(),
+            &mut fonts.system,
+            &mut self.values,
+        );
+        self.needs_layout = false;
+        // the page may have reflowed, moving the matches
+        if let Some(finding) = &mut self.finding {
+            let matches = &mut finding.matches;
+            matches.rects = self.page.find(&finding.query, finding.case_sensitive);
+            matches.active = matches
+                .active
+                .filter(|active| *active < matches.rects.len());
+        }
+        if let Some(anchor) = self.pending_anchor.take() {
+            self.scroll_to_anchor(&anchor);
+        }
+        self.scroll_to(self.scroll_y);
+    }
+
+    fn paint(&mut self, fonts: &mut Fonts) {
+        self.layout(fonts);
+        let mut canvas = Canvas::new(self.size.width, self.size.height);
+        self.page.paint(
+            &mut fonts.system,
+            &mut fonts.cache,
+            &mut canvas,
+            self.scroll_y,
+            self.focus.filter(|_| self.has_focus),
+            self.finding.as_ref().map(|finding| &finding.matches),
+        );
+        self.last_frame = ImageInfo::new(
+            canvas.pixels,
+            PixelFormat::Rgba,
+            canvas.width,
+            canvas.height,
+        );
+        self.needs_paint = false;
+    }
+
+    /// Paints the whole page into one image, as tall as the page but at least as tall as the view
+    fn paint_full_page(&mut self, fonts: &mut Fonts) -> ImageInfo {
+        self.layout(fonts);
+        let height = (self.page.height.ceil() as u32)
+            .max(self.size.height)
+            .min(MAX_CAPTURE_HEIGHT);
+        let mut canvas = Canvas::new(self.size.width, height);
+        self.page.paint(
+            &mut fonts.system,
+            &mut fonts.cache,
+            &mut canvas,
+            0.0,
+            None,
+            None,
+        );
+        ImageInfo::new(
+            canvas.pixels,
+            PixelFormat::Rgba,
+            canvas.width,
+            canvas.height,
+        )
+    }
+
+    /// Moves keyboard focus to the next (or previous) link or form field
+    fn cycle_focus(&mut self, backwards: bool) {
+        let focusables = &self.page.focusables;
+        if focusables.is_empty() {
+            return;
+        }
+        let current = self
+            .focus
+            .and_then(|focus| focusables.iter().position(|f| *f == focus));
+        let next = match (current, backwards) {
+            (None, false) => 0,
+            (None, true) => focusables.len() - 1,
+            (Some(i), false) => (i + 1) % focusables.len(),
+            (Some(i), true) => (i + focusables.len() - 1) % focusables.len(),
+        };
+        self.focus = Some(focusables[next]);
+        if let Some(rect) = self.page.focusable_rect(focusables[next]) {
+            self.scroll_into_view(rect);
+        }
+        self.needs_paint = true;
+    }
+
+    /// Scrolls an area of the page a third of the way down the view, unless it is already in view
+    fn scroll_into_view(&mut self, rect: Rectangle) {
+        let height = self.size.height as f32;
+        if rect.y < self.scroll_y || rect.y + rect.height > self.scroll_y + height {
+            self.scroll_to(rect.y - height / 3.0);
+        }
+    }
+
+    /// Selects the next or previous match of `query`, starting over for a new query
+    fn find(&mut self, fonts: &mut Fonts, query: &str, case_sensitive: bool, forward: bool) {
+        self.layout(fonts);
+        let rects = self.page.find(query, case_sensitive);
+        let last = self
+            .finding
+            .take()
+            .filter(|finding| finding.query == query && finding.case_sensitive == case_sensitive)
+            .and_then(|finding| finding.matches.active);
+        let count = rects.len();
+        let active = match (last, count) {
+            (_, 0) => None,
+            (Some(last), _) if forward => Some((last + 1) % count),
+            (Some(last), _) => Some((last + count - 1) % count),
+            (None, _) if forward => Some(0),
+            (None, _) => Some(count - 1),
+        };
+
+        if let Some(active) = active {
+            self.scroll_into_view(rects[active]);
+        }
+        self.events.push(ViewEvent::FindResult(FindResult {
+            query: query.to_string(),
+            case_sensitive,
+            active_match: active.map_or(0, |active| active + 1),
+            total_matches: count,
+        }));
+        self.finding = Some(Finding {
+            query: query.to_string(),
+            case_sensitive,
+            matches: Matches { rects, active },
+        });
+        self.needs_paint = true;
+    }
+
+    fn handle_key(
+        &mut self,
+        key: Key,
+        modifiers: keyboard::Modifiers,
+        text: Option<&str>,
+    ) -> Result<(), Error> {
+        if key == Key::Named(Named::Tab) {
+            self.cycle_focus(modifiers.shift());
+            return Ok(());
+        }
+
+        match self.focus {
+            Some(Focusable::Field(index)) => {
+                let kind = self.page.fields[index].kind;
+                let value = &mut self.values[index];
+                match key {
+                    Key::Named(Named::Escape) => self.focus = None,
+                    Key::Named(Named::Backspace) if kind.is_editable() => {
+                        value.text.pop();
+                    }
+                    Key::Named(Named::Enter) if kind == FieldKind::TextArea => {
+                        value.text.push('\n')
+                    }
+                    Key::Named(Named::Space) | Key::Named(Named::Enter)
+                        if kind == FieldKind::Checkbox =>
+                    {
+                        value.checked = !value.checked
+                    }
+                    _ if kind.is_editable() && !(modifiers.control() || modifiers.logo()) => {
+                        match text {
+                            Some(text) => {
+                                value.text.extend(text.chars().filter(|c| !c.is_control()))
+                            }
+                            None => return Ok(()),
+                        }
+                    }
+                    _ => return Ok(()),
+                }
+                self.needs_layout = true;
+                self.needs_paint = true;
+                return Ok(());
+            }
+            Some(Focusable::Link(index)) if key == Key::Named(Named::Enter) => {
+                return self.activate_link(index);
+            }
+            _ => (),
+        }
+
+        let page = self.size.height as f32 * 0.9;
+        let scroll = match key {
+            Key::Named(Named::ArrowDown) => SCROLL_LINE * self.scale(),
+            Key::Named(Named::ArrowUp) => -SCROLL_LINE * self.scale(),
+            Key::Named(Named::PageDown) => page,
+            Key::Named(Named::PageUp) => -page,
+            Key::Named(Named::Space) if modifiers.shift() => -page,
+            Key::Named(Named::Space) => page,
+            Key::Named(Named::Home) => -self.page.height,
+            Key::Named(Named::End) => self.page.height,
+            _ => return Ok(()),
+        };
+        self.scroll_to(self.scroll_y + scroll);
+        Ok(())
+    }
+
+    fn handle_mouse(&mut self, point: Point, event: mouse::Event) -> Result<(), Error> {
+        // the point in page coordinates, rather than the viewport
+        let target = Point::new(point.x, point.y + self.scroll_y);
+        match event {
+            mouse::Event::CursorMoved { .. } => {
+                self.cursor = if self.page.link_at(target).is_some() {
+                    Interaction::Pointer
+                } else if let Some(field) = self.page.field_at(target) {
+                    if self.page.fields[field].kind.is_editable() {
+                        Interaction::Text
+                    } else {
+                        Interaction::Pointer
+                    }
+                } else {
+                    Interaction::Idle
+                };
+            }
+            mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                self.needs_paint = true;
+                if let Some(link) = self.page.link_at(target) {
+                    self.focus = Some(Focusable::Link(link));
+                    return self.activate_link(link);
+                } else if let Some(field) = self.page.field_at(target) {
+                    self.focus = Some(Focusable::Field(field));
+                    if self.page.fields[field].kind == FieldKind::Checkbox {
+                        self.values[field].checked = !self.values[field].checked;
+                        self.needs_layout = true;
+                    }
+                } else {
+                    self.focus = None;
+                }
+            }
+            mouse::Event::ButtonReleased(mouse::Button::Back) => self.go_back(),
+            mouse::Event::ButtonReleased(mouse::Button::Forward) => self.go_forward(),
+            mouse::Event::WheelScrolled { delta } => self.scroll(delta),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn scroll(&mut self, delta: ScrollDelta) {
+        let y = match delta {
+            ScrollDelta::Lines { y, .. } => y * SCROLL_LINE * self.scale(),
+            ScrollDelta::Pixels { y, .. } => y,
+        };
+        self.scroll_to(self.scroll_y - y);
+    }
+}
+
+/// A pure Rust implementation of Engine for local content
+///
+/// Renders `PageType::Html` and `file://` pages with a small HTML/CSS layout engine on the cpu.
+/// It supports block and inline text, headings, lists, links, inline and `<style>` css,
+/// and text inputs, text areas and checkboxes. There is no networking, JavaScript,
+/// images or form submission, which makes it a good fit for offline documentation and help screens.
+pub struct PureHtml {
+    fonts: Fonts,
+    views: Vec<View>,
+    assets: Assets,
+    intercept_navigation: bool,
+    // clear_data calls waiting to be reported
+    cleared_data: Vec<Result<(), Error>>,
+}
+
+impl Default for PureHtml {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PureHtml {
+    /// Creates a new engine using the system's fonts
+    pub fn new() -> Self {
+        Self {
+            fonts: Fonts {
+                system: FontSystem::new(),
+                cache: SwashCache::new(),
+            },
+            views: Vec::new(),
+            assets: Assets::new(),
+            intercept_navigation: false,
+            cleared_data: Vec::new(),
+        }
+    }
+
+    fn get_view(&self, id: ViewId) -> Result<&View, Error> {
+        self.views
+            .iter()
+            .find(|view| view.id == id)
+            .ok_or(Error::UnknownView(id))
+    }
+
+    fn get_view_mut(&mut self, id: ViewId) -> Result<&mut View, Error> {
+        self.views
+            .iter_mut()
+            .find(|view| view.id == id)
+            .ok_or(Error::UnknownView(id))
+    }
+
+    fn get_view_and_fonts(&mut self, id: ViewId) -> Result<(&mut View, &mut Fonts), Error> {
+        let view = self
+            .views
+            .iter_mut()
+            .find(|view| view.id == id)
+            .ok_or(Error::UnknownView(id))?;
+        Ok((view, &mut self.fonts))
+    }
+}
+
+impl Engine for PureHtml {
+    fn update(&mut self) {
+        // pages are static, so there is no background work to do
+    }
+
+    fn render(&mut self) {
+        for view in self.views.iter_mut() {
+            if view.needs_paint || view.needs_layout {
+                view.paint(&mut self.fonts);
+            }
+        }
+    }
+
+    fn take_events(&mut self) -> Vec<(ViewId, ViewEvent)> {
+        self.views
+            .iter_mut()
+            .flat_map(|view| view.events.drain(..).map(|event| (view.id, event)))
+            .collect()
+    }
+
+    fn needs_update(&self) -> bool {
+        self.views
+            .iter()
+            .any(|view| view.needs_paint || view.needs_layout)
+    }
+
+    fn request_render(&mut self, id: ViewId) -> Result<(), Error> {
+        let (view, fonts) = self.get_view_and_fonts(id)?;
+        if view.needs_paint || view.needs_layout {
+            view.paint(fonts);
+        }
+        Ok(())
+    }
+
+    fn new_view(
+        &mut self,
+        size: Size<u32>,
+        content: Option<PageType>,
+        session: Session,
+    ) -> Result<ViewId, Error> {
+        let id = rand::thread_rng().gen();
+
+        let mut view = View {
+            id,
+            history: Vec::new(),
+            history_index: 0,
+            document: Document::parse("", None, &self.assets),
+            values: Vec::new(),
+            page: Page::empty(),
+            size,
+            scale_factor: 1.0,
+            zoom: 1.0,
+            scroll_y: 0.0,
+            pending_anchor: None,
+            cursor: Interaction::Idle,
+            focus: None,
+            has_focus: false,
+            needs_layout: true,
+            needs_paint: true,
+            last_frame: ImageInfo::blank(size.width, size.height),
+            events: Vec::new(),
+            assets: self.assets.clone(),
+            intercept_navigation: self.intercept_navigation,
+            session,
+            finding: None,
+        };
+        if let Some(page_type) = content {
+            view.navigate(page_type);
+        }
+        self.views.push(view);
+        Ok(id)
+    }
+
+    fn remove_view(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view(id)?;
+        self.views.retain(|view| view.id != id);
+        Ok(())
+    }
+
+    fn focus(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.has_focus = true;
+        view.needs_paint = true;
+        Ok(())
+    }
+
+    fn unfocus(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.has_focus = false;
+        view.needs_paint = true;
+        Ok(())
+    }
+
+    fn has_focus(&self, id: ViewId) -> Result<bool, Error> {
+        Ok(self.get_view(id)?.has_focus)
+    }
+
+    fn resize(&mut self, id: ViewId, size: Size<u32>) -> Result<(), Error> {
+        self.get_view_mut(id)?.resize(size);
+        Ok(())
+    }
+
+    fn set_scale_factor(&mut self, id: ViewId, scale_factor: f32) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.set_scale(scale_factor, view.zoom);
+        Ok(())
+    }
+
+    fn set_zoom(&mut self, id: ViewId, zoom: f32) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.set_scale(view.scale_factor, zoom);
+        Ok(())
+    }
+
+    fn handle_keyboard_event(&mut self, id: ViewId, event: keyboard::Event) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        match event {
+            keyboard::Event::KeyPressed {
+                key,
+                modifiers,
+                text,
+                ..
+            } => view.handle_key(key, modifiers, text.as_deref()),
+            _ => Ok(()),
+        }
+    }
+
+    fn handle_mouse_event(
+        &mut self,
+        id: ViewId,
+        point: Point,
+        event: mouse::Event,
+    ) -> Result<(), Error> {
+        self.get_view_mut(id)?.handle_mouse(point, event)
+    }
+
+    fn scroll(&mut self, id: ViewId, delta: mouse::ScrollDelta) -> Result<(), Error> {
+        self.get_view_mut(id)?.scroll(delta);
+        Ok(())
+    }
+
+    fn set_assets(&mut self, assets: Assets) {
+        for view in self.views.iter_mut() {
+            view.assets = assets.clone();
+        }
+        self.assets = assets;
+    }
+
+    fn set_scheme(&mut self, _scheme: &str) {
+        // pages can only make requests from scripts, which are not supported
+    }
+
+    fn respond(
+        &mut self,
+        id: ViewId,
+        _request: RequestId,
+        _response: Response,
+    ) -> Result<(), Error> {
+        self.get_view(id)?;
+        Ok(())
+    }
+
+    fn answer_dialog(
+        &mut self,
+        id: ViewId,
+        _dialog: DialogId,
+        _answer: DialogAnswer,
+    ) -> Result<(), Error> {
+        // dialogs are opened from scripts, which are not supported
+        self.get_view(id)?;
+        Ok(())
+    }
+
+    fn intercept_navigation(&mut self, intercept: bool) {
+        for view in self.views.iter_mut() {
+            view.intercept_navigation = intercept;
+        }
+        self.intercept_navigation = intercept;
+    }
+
+    /// There are no cookies, cache or storage to clear
+    fn clear_data(&mut self, _session: Option<Session>, _data: BrowsingData) {
+        // pages can not store anything without scripts or networking
+        self.cleared_data.push(Ok(()));
+    }
+
+    fn take_cleared_data(&mut self) -> Vec<Result<(), Error>> {
+        std::mem::take(&mut self.cleared_data)
+    }
+
+    fn goto(&mut self, id: ViewId, page_type: PageType) -> Result<(), Error> {
+        self.get_view_mut(id)?.navigate(page_type);
+        Ok(())
+    }
+
+    fn refresh(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.go_to_history(view.history_index);
+        Ok(())
+    }
+
+    fn go_forward(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.go_forward();
+        Ok(())
+    }
+
+    fn go_back(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.go_back();
+        Ok(())
+    }
+
+    fn evaluate_script(&mut self, id: ViewId, _script: &str) -> Result<(), Error> {
+        self.get_view_mut(id)?
+            .events
+            .push(ViewEvent::ScriptEvaluated(Err(
+                "JavaScript is not supported by the PureHtml engine".to_string(),
+            )));
+        Ok(())
+    }
+
+    fn post_message(&mut self, id: ViewId, _message: &str) -> Result<(), Error> {
+        // without JavaScript there is nothing in the page to receive it
+        self.get_view(id)?;
+        Ok(())
+    }
+
+    /// Every match is highlighted, with the selected one standing out
+    fn find(
+        &mut self,
+        id: ViewId,
+        query: &str,
+        case_sensitive: bool,
+        forward: bool,
+    ) -> Result<(), Error> {
+        let (view, fonts) = self.get_view_and_fonts(id)?;
+        view.find(fonts, query, case_sensitive, forward);
+        Ok(())
+    }
+
+    fn stop_finding(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.finding = None;
+        view.needs_paint = true;
+        Ok(())
+    }
+
+    fn get_url(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self
+            .get_view(id)?
+            .document
+            .url
+            .as_ref()
+            .map(Url::to_string)
+            .unwrap_or_default())
+    }
+
+    fn get_title(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self.get_view(id)?.document.title.clone())
+    }
+
+    fn get_session(&self, id: ViewId) -> Result<Session, Error> {
+        Ok(self.get_view(id)?.session.clone())
+    }
+
+    fn get_cursor(&self, id: ViewId) -> Result<Interaction, Error> {
+        Ok(self.get_view(id)?.cursor)
+    }
+
+    fn get_zoom(&self, id: ViewId) -> Result<f32, Error> {
+        Ok(self.get_view(id)?.zoom)
+    }
+
+    fn get_view(&self, id: ViewId) -> Result<&ImageInfo, Error> {
+        Ok(&self.get_view(id)?.last_frame)
+    }
+
+    fn capture_full_page(&mut self, id: ViewId) -> Result<(), Error> {
+        let (view, fonts) = self.get_view_and_fonts(id)?;
+        let image = view.paint_full_page(fonts);
+        view.events.push(ViewEvent::FullPageCaptured(image));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: Size<u32> = Size::new(400, 300);
+
+    /// An engine with `index.html` as an asset, and a view showing it
+    fn engine(index: &str) -> (PureHtml, ViewId) {
+        let mut engine = PureHtml::new();
+        engine.set_assets(
+            Assets::new()
+                .with("index.html", index.as_bytes().to_vec())
+                .with("next.html", b"<title>Next</title><p>Next page</p>".to_vec()),
+        );
+        let page = PageType::Url(Assets::url("index.html"));
+        let id = engine.new_view(SIZE, Some(page), Session::Default).unwrap();
+        engine.request_render(id).unwrap();
+        (engine, id)
+    }
+
+    fn events(engine: &mut PureHtml) -> Vec<ViewEvent> {
+        engine
+            .take_events()
+            .into_iter()
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Clicks the middle of where `text` is shown
+    fn click(engine: &mut PureHtml, id: ViewId, text: &str) {
+        let view = engine.get_view(id).unwrap();
+        let rect = view.page.find(text, true)[0];
+        let point = Point::new(rect.center_x(), rect.center_y() - view.scroll_y);
+        let event = mouse::Event::ButtonPressed(mouse::Button::Left);
+        engine.handle_mouse_event(id, point, event).unwrap();
+        engine.request_render(id).unwrap();
+    }
+
+    /// A page taller than the view, with a heading at its end
+    fn long_page(links: &str) -> String {
+        format!("{links}{}<h2 id='end'>End</h2>", "<p>Filler</p>".repeat(40))
+    }
+
+    #[test]
+    fn pages_load_at_once() {
+        let (mut engine, id) = engine("<title> Index </title><p>Hello</p>");
+        let url = Assets::url("index.html");
+        assert_eq!(
+            events(&mut engine),
+            [
+                ViewEvent::LoadStarted(url.clone()),
+                ViewEvent::DomReady(url.clone()),
+                ViewEvent::LoadFinished(url.clone()),
+                ViewEvent::HistoryChanged,
+            ]
+        );
+        assert_eq!(engine.get_url(id).unwrap(), url);
+        assert_eq!(engine.get_title(id).unwrap(), "Index");
+
+        let frame = Engine::get_view(&engine, id).unwrap();
+        assert_eq!((frame.width(), frame.height()), (SIZE.width, SIZE.height));
+    }
+
+    #[test]
+    fn clicking_links_follows_them() {
+        let (mut engine, id) = engine("<a href='next.html'>Next</a>");
+        events(&mut engine);
+
+        click(&mut engine, id, "Next");
+        assert_eq!(engine.get_url(id).unwrap(), Assets::url("next.html"));
+        assert_eq!(engine.get_title(id).unwrap(), "Next");
+        assert!(events(&mut engine).contains(&ViewEvent::HistoryChanged));
+
+        engine.go_back(id).unwrap();
+        assert_eq!(engine.get_url(id).unwrap(), Assets::url("index.html"));
+        engine.go_forward(id).unwrap();
+        assert_eq!(engine.get_url(id).unwrap(), Assets::url("next.html"));
+    }
+
+    #[test]
+    fn intercepted_and_new_window_links_are_left_to_the_app() {
+        let (mut engine, id) =
+            engine("<a href='next.html'>Next</a> <a href='next.html' target='_blank'>Popup</a>");
+        engine.intercept_navigation(true);
+        events(&mut engine);
+
+        click(&mut engine, id, "Next");
+        click(&mut engine, id, "Popup");
+        let url = Assets::url("next.html");
+        assert_eq!(
+            events(&mut engine),
+            [
+                ViewEvent::NavigationRequested(url.clone(), NavigationKind::LinkClick),
+                ViewEvent::NewWindowRequested(
+                    url,
+                    Opener {
+                        url: Assets::url("index.html"),
+                        is_popup: false,
+                        rect: None,
+                    }
+                ),
+            ]
+        );
+        assert_eq!(engine.get_url(id).unwrap(), Assets::url("index.html"));
+    }
+
+    #[test]
+    fn anchors_scroll_the_page() {
+        let (mut engine, id) = engine(&long_page("<a href='#end'>Jump</a>"));
+
+        click(&mut engine, id, "Jump");
+        let view = engine.get_view(id).unwrap();
+        let end = view.page.anchor("end").unwrap();
+        let max = view.page.height - SIZE.height as f32;
+        assert_eq!(view.scroll_y, end.min(max));
+        assert!(view.scroll_y > 0.0);
+
+        // a url's fragment is scrolled to once the page is laid out
+        let page = PageType::Url(format!("{}#end", Assets::url("index.html")));
+        engine.goto(id, page).unwrap();
+        engine.request_render(id).unwrap();
+        assert!(engine.get_view(id).unwrap().scroll_y > 0.0);
+    }
+
+    #[test]
+    fn find_steps_through_matches() {
+        let (mut engine, id) = engine(&long_page("<p>Filler is filler</p>"));
+        events(&mut engine);
+
+        let mut find = |forward| {
+            engine.find(id, "filler", false, forward).unwrap();
+            match events(&mut engine).pop() {
+                Some(ViewEvent::FindResult(result)) => (result.active_match, result.total_matches),
+                event => panic!("expected a find result, got {event:?}"),
+            }
+        };
+        assert_eq!(find(true), (1, 42));
+        assert_eq!(find(true), (2, 42));
+        assert_eq!(find(false), (1, 42));
+        assert_eq!(find(false), (42, 42));
+        // the last match is at the bottom of the page
+        assert!(engine.get_view(id).unwrap().scroll_y > 0.0);
+
+        engine.find(id, "missing", false, true).unwrap();
+        assert!(matches!(
+            events(&mut engine)[..],
+            [ViewEvent::FindResult(FindResult {
+                active_match: 0,
+                total_matches: 0,
+                ..
+            })]
+        ));
+    }
+
+    #[test]
+    fn unsupported_content_is_reported() {
+        let (mut engine, id) = engine("<p>Hello</p>");
+        events(&mut engine);
+
+        let url = "https://example.com/".to_string();
+        engine.goto(id, PageType::Url(url.clone())).unwrap();
+        assert!(matches!(
+            &events(&mut engine)[..],
+            [ViewEvent::LoadStarted(_), ViewEvent::LoadFailed(failure)] if failure.url == url
+        ));
+        // the page that was shown stays
+        assert_eq!(engine.get_url(id).unwrap(), Assets::url("index.html"));
+
+        engine.evaluate_script(id, "1 + 1").unwrap();
+        assert!(matches!(
+            events(&mut engine)[..],
+            [ViewEvent::ScriptEvaluated(Err(_))]
+        ));
+        assert_eq!(engine.get_url(0), Err(Error::UnknownView(0)));
+    }
+}
@@ -0,0 +1,991 @@
+use cosmic_text::{Align, Attrs, Buffer, Family, FontSystem, Metrics, Shaping, SwashCache, Weight};
+use iced::{Point, Rectangle};
+use scraper::{node::Node, ElementRef, Html, Selector};
+
+use super::style::{Color, Display, Style, Stylesheet, TextAlign, WHITE};
+
+const LINE_HEIGHT: f32 = 1.2;
+const FIELD_BORDER: Color = [118, 118, 118, 255];
+const FIELD_BUTTON: Color = [239, 239, 239, 255];
+const FOCUS_RING: Color = [16, 110, 190, 255];
+const FIND_MATCH: Color = [255, 235, 59, 110];
+const FIND_ACTIVE_MATCH: Color = [255, 150, 0, 150];
+
+/// The value a form field holds, kept by the view so it survives relayouts
+#[derive(Clone, Debug, Default)]
+pub(super) struct FieldValue {
+    pub text: String,
+    pub checked: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum FieldKind {
+    Text,
+    Password,
+    TextArea,
+    Checkbox,
+    Button,
+}
+
+impl FieldKind {
+    pub fn is_editable(self) -> bool {
+        matches!(
+            self,
+            FieldKind::Text | FieldKind::Password | FieldKind::TextArea
+        )
+    }
+}
+
+pub(super) struct Field {
+    pub kind: FieldKind,
+    pub rect: Rectangle,
+}
+
+pub(super) struct Link {
+    pub href: String,
+    // set by target="_blank"
+    pub new_window: bool,
+    pub rects: Vec<Rectangle>,
+}
+
+/// Something that can receive keyboard focus, in document order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Focusable {
+    Link(usize),
+    Field(usize),
+}
+
+enum Paint {
+    Fill {
+        rect: Rectangle,
+        color: Color,
+    },
+    Text {
+        buffer: Box<Buffer>,
+        origin: Point,
+        clip: Option<Rectangle>,
+    },
+}
+
+/// Where a find in page matched, highlighted when the page is painted
+pub(super) struct Matches {
+    pub rects: Vec<Rectangle>,
+    // the selected match, highlighted differently from the rest
+    pub active: Option<usize>,
+}
+
+/// A page laid out at a fixed width, ready to be painted at any scroll offset
+pub(super) struct Page {
+    pub height: f32,
+    pub links: Vec<Link>,
+    pub fields: Vec<Field>,
+    pub focusables: Vec<Focusable>,
+    anchors: Vec<(String, f32)>,
+    paint: Vec<Paint>,
+}
+
+impl Page {
+    pub fn empty() -> Self {
+        Self {
+            height: 0.0,
+            links: Vec::new(),
+            fields: Vec::new(),
+            focusables: Vec::new(),
+            anchors: Vec::new(),
+            paint: Vec::new(),
+        }
+    }
+
+    /// Lays out `document` to fit `width`, with a css pixel covering `scale` pixels
+    ///
+    /// `values` holds the state of every form field in document order. Fields that are not
+    /// in it yet are added with the value the document gives them
+    pub fn layout(
+        document: &Html,
+        stylesheet: &Stylesheet,
+        width: f32,
+        scale: f32,
+        font_system: &mut FontSystem,
+        values: &mut Vec<FieldValue>,
+    ) -> Self {
+        let mut builder = Builder {
+            font_system,
+            stylesheet,
+            values,
+            width,
+            y: 0.0,
+            left: 0.0,
+            right: 0.0,
+            pending_margin: 0.0,
+            spans: Vec::new(),
+            last_was_space: true,
+            lists: Vec::new(),
+            page: Page::empty(),
+        };
+        let root = Style::root(scale);
+        builder.element(document.root_element(), &root, None);
+        builder.flush_inline(&root);
+        builder.page.height = builder.y + builder.pending_margin;
+        builder.page
+    }
+
+    /// The vertical offset of the element with the given `id` or `name`
+    pub fn anchor(&self, name: &str) -> Option<f32> {
+        self.anchors
+            .iter()
+            .find(|(anchor, _)| anchor == name)
+            .map(|(_, y)| *y)
+    }
+
+    pub fn link_at(&self, point: Point) -> Option<usize> {
+        self.links
+            .iter()
+            .position(|link| link.rects.iter().any(|rect| rect.contains(point)))
+    }
+
+    pub fn field_at(&self, point: Point) -> Option<usize> {
+        self.fields
+            .iter()
+            .position(|field| field.rect.contains(point))
+    }
+
+    /// The area `focusable` takes up, used to scroll it into view
+    pub fn focusable_rect(&self, focusable: Focusable) -> Option<Rectangle> {
+        match focusable {
+            Focusable::Link(link) => self.links.get(link)?.rects.first().copied(),
+            Focusable::Field(field) => Some(self.fields.get(field)?.rect),
+        }
+    }
+
+    /// The areas of every match of `query` in the page's text, in the order they were laid out
+    ///
+    /// Only text within a single line is matched
+    pub fn find(&self, query: &str, case_sensitive: bool) -> Vec<Rectangle> {
+        let mut matches = Vec::new();
+        if query.is_empty() {
+            return matches;
+        }
+        for paint in self.paint.iter() {
+            let Paint::Text { buffer, origin, .. } = paint else {
+                continue;
+            };
+            for run in buffer.layout_runs() {
+                let mut start = 0;
+                while let Some((at, len)) = find_text(&run.text[start..], query, case_sensitive) {
+                    let (from, to) = (start + at, start + at + len);
+                    start = to;
+                    // wrapped lines share their text, so matches belong to the run they start in
+                    if !run.glyphs.iter().any(|g| g.start <= from && from < g.end) {
+                        continue;
+                    }
+                    let (left, right) = run
+                        .glyphs
+                        .iter()
+                        .filter(|g| g.start < to && g.end > from)
+                        .fold((f32::MAX, f32::MIN), |(left, right), g| {
+                            (left.min(g.x), right.max(g.x + g.w))
+                        });
+                    matches.push(Rectangle::new(
+                        Point::new(origin.x + left, origin.y + run.line_top),
+                        iced::Size::new(right - left, run.line_height),
+                    ));
+                }
+            }
+        }
+        matches
+    }
+
+    /// Paints the part of the page visible at `scroll_y` onto `canvas`
+    pub fn paint(
+        &self,
+        font_system: &mut FontSystem,
+        swash_cache: &mut SwashCache,
+        canvas: &mut Canvas,
+        scroll_y: f32,
+        focus: Option<Focusable>,
+        matches: Option<&Matches>,
+    ) {
+        canvas.clear(WHITE);
+        let viewport = Rectangle::new(
+            Point::new(0.0, scroll_y),
+            iced::Size::new(canvas.width as f32, canvas.height as f32),
+        );
+
+        for paint in self.paint.iter() {
+            match paint {
+                Paint::Fill { rect, color } => {
+                    if rect.intersects(&viewport) {
+                        canvas.fill(rect.x, rect.y - scroll_y, rect.width, rect.height, *color)
+                    }
+                }
+                Paint::Text {
+                    buffer,
+                    origin,
+                    clip,
+                } => {
+                    let height = buffer
+                        .layout_runs()
+                        .last()
+                        .map(|run| run.line_top + run.line_height)
+                        .unwrap_or_default();
+                    if origin.y > viewport.y + viewport.height || origin.y + height < viewport.y {
+                        continue;
+                    }
+                    buffer.draw(
+                        font_system,
+                        swash_cache,
+                        cosmic_text::Color::rgb(0, 0, 0),
+                        |x, y, w, h, color| {
+                            let point = Point::new(origin.x + x as f32, origin.y + y as f32);
+                            if clip.is_some_and(|clip| !clip.contains(point)) {
+                                return;
+                            }
+                            canvas.fill(
+                                point.x,
+                                point.y - scroll_y,
+                                w as f32,
+                                h as f32,
+                                color.as_rgba(),
+                            )
+                        },
+                    );
+                }
+            }
+        }
+
+        for (index, rect) in matches
+            .iter()
+            .flat_map(|matches| matches.rects.iter().enumerate())
+        {
+            if rect.intersects(&viewport) {
+                let color = if matches.is_some_and(|matches| matches.active == Some(index)) {
+                    FIND_ACTIVE_MATCH
+                } else {
+                    FIND_MATCH
+                };
+                canvas.fill(rect.x, rect.y - scroll_y, rect.width, rect.height, color);
+            }
+        }
+
+        if let Some(rect) = focus.and_then(|focus| self.focusable_rect(focus)) {
+            canvas.outline(
+                Rectangle {
+                    y: rect.y - scroll_y,
+                    ..rect
+                },
+                FOCUS_RING,
+            );
+        }
+    }
+}
+
+/// Where `needle` first appears in `text`, as its byte offset and byte length in `text`
+fn find_text(text: &str, needle: &str, case_sensitive: bool) -> Option<(usize, usize)> {
+    if case_sensitive {
+        return text.find(needle).map(|at| (at, needle.len()));
+    }
+    // lowercasing can change how long a character is, so the text is compared as it goes
+    text.char_indices().find_map(|(at, _)| {
+        let mut needle = needle.chars().flat_map(char::to_lowercase).peekable();
+        for (offset, c) in text[at..].char_indices() {
+            for lower in c.to_lowercase() {
+                if needle.next() != Some(lower) {
+                    return None;
+                }
+            }
+            if needle.peek().is_none() {
+                return Some((at, offset + c.len_utf8()));
+            }
+        }
+        None
+    })
+}
+
+/// A RGBA pixel buffer
+pub(super) struct Canvas {
+    pub pixels: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            pixels: vec![255; width as usize * height as usize * 4],
+            width,
+            height,
+        }
+    }
+
+    fn clear(&mut self, color: Color) {
+        self.pixels
+            .chunks_exact_mut(4)
+            .for_each(|pixel| pixel.copy_from_slice(&color));
+    }
+
+    /// Alpha blends `color` over the given area, clipped to the canvas
+    fn fill(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        let alpha = color[3] as u32;
+        if alpha == 0 {
+            return;
+        }
+        let x0 = (x.round().max(0.0) as u32).min(self.width);
+        let y0 = (y.round().max(0.0) as u32).min(self.height);
+        let x1 = ((x + width).round().max(0.0) as u32).min(self.width);
+        let y1 = ((y + height).round().max(0.0) as u32).min(self.height);
+
+        for row in y0..y1 {
+            let start = (row * self.width + x0) as usize * 4;
+            let end = (row * self.width + x1) as usize * 4;
+            for pixel in self.pixels[start..end].chunks_exact_mut(4) {
+                for channel in 0..3 {
+                    pixel[channel] = ((color[channel] as u32 * alpha
+                        + pixel[channel] as u32 * (255 - alpha))
+                        / 255) as u8;
+                }
+                pixel[3] = 255;
+            }
+        }
+    }
+
+    fn outline(&mut self, rect: Rectangle, color: Color) {
+        let Rectangle {
+            x,
+            y,
+            width,
+            height,
+        } = rect;
+        self.fill(x - 2.0, y - 2.0, width + 4.0, 2.0, color);
+        self.fill(x - 2.0, y + height, width + 4.0, 2.0, color);
+        self.fill(x - 2.0, y, 2.0, height, color);
+        self.fill(x + width, y, 2.0, height, color);
+    }
+}
+
+struct Span {
+    text: String,
+    style: Style,
+    link: Option<usize>,
+}
+
+struct Builder<'a> {
+    font_system: &'a mut FontSystem,
+    stylesheet: &'a Stylesheet,
+    values: &'a mut Vec<FieldValue>,
+    width: f32,
+    y: f32,
+    left: f32,
+    right: f32,
+    // the bottom margin of the previous block, collapsed with the next top margin
+    pending_margin: f32,
+    spans: Vec<Span>,
+    last_was_space: bool,
+    // `None` for unordered lists, otherwise the next number of an ordered list
+    lists: Vec<Option<usize>>,
+    page: Page,
+}
+
+impl Builder<'_> {
+    /// Lays out `element` and its children. `link` is the link it is nested in, if any
+    fn element(&mut self, element: ElementRef, parent: &Style, link: Option<usize>) {
+        let style = Style::compute(&element, parent, self.stylesheet);
+        if style.display == Display::None {
+            return;
+        }
+
+        match element.value().name() {
+            "br" => {
+                self.text("\n", &style, link, true);
+                return;
+            }
+            "img" => {
+                if let Some(alt) = element.value().attr("alt") {
+                    self.text(alt, &style, link, false);
+                }
+                return;
+            }
+            "hr" => {
+                self.flush_inline(parent);
+                self.margin(style.margin_top);
+                self.push_fill(
+                    Rectangle::new(
+                        Point::new(self.left, self.y),
+                        iced::Size::new(self.available_width(), style.scale),
+                    ),
+                    FIELD_BORDER,
+                );
+                self.y += style.scale;
+                self.pending_margin = style.margin_bottom;
+                return;
+            }
+            "select" => {
+                let selected = Selector::parse("option[selected]")
+                    .ok()
+                    .and_then(|selector| element.select(&selector).next())
+                    .or_else(|| {
+                        Selector::parse("option")
+                            .ok()
+                            .and_then(|selector| element.select(&selector).next())
+                    })
+                    .map(|option| option.text().collect::<String>())
+                    .unwrap_or_default();
+                self.text(&format!("[{}]", selected.trim()), &style, link, false);
+                return;
+            }
+            "input" | "textarea" | "button" => {
+                self.field(element, &style, parent);
+                return;
+            }
+            _ => (),
+        }
+
+        let is_block = matches!(style.display, Display::Block | Display::ListItem);
+        if is_block {
+            self.flush_inline(parent);
+            self.pending_margin = self.pending_margin.max(style.margin_top);
+        }
+
+        let anchor = element
+            .value()
+            .id()
+            .or_else(|| element.value().attr("name"));
+        if let Some(anchor) = anchor {
+            self.page
+                .anchors
+                .push((anchor.to_string(), self.y + self.pending_margin));
+        }
+
+        let (left, right) = (self.left, self.right);
+        let background = if is_block {
+            style.background.map(|color| {
+                self.margin(0.0);
+                self.page.paint.push(Paint::Fill {
+                    rect: Rectangle::default(),
+                    color,
+                });
+                (self.page.paint.len() - 1, self.y)
+            })
+        } else {
+            None
+        };
+        if is_block {
+            if style.padding > 0.0 {
+                self.margin(0.0);
+                self.y += style.padding;
+            }
+            self.left += style.margin_left + style.padding;
+            self.right += style.padding;
+        }
+
+        match element.value().name() {
+            "ul" | "menu" => self.lists.push(None),
+            "ol" => {
+                let start = element
+                    .value()
+                    .attr("start")
+                    .and_then(|start| start.parse().ok())
+                    .unwrap_or(1);
+                self.lists.push(Some(start))
+            }
+            _ => (),
+        }
+        if style.display == Display::ListItem {
+            let marker = match self.lists.last_mut() {
+                Some(Some(number)) => {
+                    *number += 1;
+                    format!("{}. ", *number - 1)
+                }
+                _ => "• ".to_string(),
+            };
+            self.text(&marker, &style, None, true);
+        }
+
+        let link = match element.value().attr("href") {
+            Some(href) if element.value().name() == "a" => {
+                self.page.links.push(Link {
+                    href: href.to_string(),
+                    new_window: element.value().attr("target") == Some("_blank"),
+                    rects: Vec::new(),
+                });
+                let link = self.page.links.len() - 1;
+                self.page.focusables.push(Focusable::Link(link));
+                Some(link)
+            }
+            _ => link,
+        };
+
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.text(text, &style, link, style.preformatted),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.element(child, &style, link);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        match element.value().name() {
+            "ul" | "ol" | "menu" => {
+                self.lists.pop();
+            }
+            "td" | "th" => self.text("   ", &style, None, true),
+            _ => (),
+        }
+
+        if is_block {
+            self.flush_inline(&style);
+            if style.padding > 0.0 {
+                self.margin(0.0);
+                self.y += style.padding;
+            }
+            self.left = left;
+            self.right = right;
+            if let Some((index, top)) = background {
+                self.margin(0.0);
+                if let Some(Paint::Fill { rect, .. }) = self.page.paint.get_mut(index) {
+                    *rect = Rectangle::new(
+                        Point::new(left, top),
+                        iced::Size::new(self.width - left - right, self.y - top),
+                    );
+                }
+            }
+            self.pending_margin = self.pending_margin.max(style.margin_bottom);
+        }
+    }
+
+    fn text(&mut self, text: &str, style: &Style, link: Option<usize>, preformatted: bool) {
+        let text = if preformatted {
+            self.last_was_space = text.ends_with(char::is_whitespace);
+            text.to_string()
+        } else {
+            let mut collapsed = String::with_capacity(text.len());
+            for c in text.chars() {
+                if c.is_whitespace() {
+                    if !self.last_was_space {
+                        collapsed.push(' ');
+                        self.last_was_space = true;
+                    }
+                } else {
+                    collapsed.push(c);
+                    self.last_was_space = false;
+                }
+            }
+            collapsed
+        };
+        if !text.is_empty() {
+            self.spans.push(Span {
+                text,
+                style: style.clone(),
+                link,
+            });
+        }
+    }
+
+    fn field(&mut self, element: ElementRef, style: &Style, parent: &Style) {
+        let input_type = element
+            .value()
+            .attr("type")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let kind = match (element.value().name(), input_type.as_str()) {
+            ("input", "hidden") => return,
+            ("input", "checkbox" | "radio") => FieldKind::Checkbox,
+            ("input", "submit" | "button" | "reset" | "image") => FieldKind::Button,
+            ("input", "password") => FieldKind::Password,
+            ("input", _) => FieldKind::Text,
+            ("textarea", _) => FieldKind::TextArea,
+            _ => FieldKind::Button,
+        };
+
+        let index = self.page.fields.len();
+        if self.values.len() <= index {
+            self.values.push(FieldValue {
+                text: match kind {
+                    FieldKind::TextArea | FieldKind::Button
+                        if element.value().name() != "input" =>
+                    {
+                        element.text().collect::<String>().trim().to_string()
+                    }
+                    FieldKind::Button => element
+                        .value()
+                        .attr("value")
+                        .unwrap_or(if input_type == "reset" {
+                            "Reset"
+                        } else {
+                            "Submit"
+                        })
+                        .to_string(),
+                    _ => element
+                        .value()
+                        .attr("value")
+                        .unwrap_or_default()
+                        .to_string(),
+                },
+                checked: element.value().attr("checked").is_some(),
+            });
+        }
+        let value = self.values[index].clone();
+
+        self.flush_inline(parent);
+        self.margin(0.0);
+
+        let line_height = style.font_size * LINE_HEIGHT;
+        let char_width = style.font_size * 0.55;
+        // a css pixel, for the field's fixed sizes
+        let px = style.scale;
+        let attr = |name: &str, default: f32| {
+            element
+                .value()
+                .attr(name)
+                .and_then(|value| value.parse::<f32>().ok())
+                .unwrap_or(default)
+        };
+        let text = match kind {
+            FieldKind::Password => "•".repeat(value.text.chars().count()),
+            _ => value.text.clone(),
+        };
+        let size = match kind {
+            FieldKind::Checkbox => iced::Size::new(13.0 * px, 13.0 * px),
+            FieldKind::Text | FieldKind::Password => iced::Size::new(
+                attr("size", 20.0) * char_width + 8.0 * px,
+                line_height + 8.0 * px,
+            ),
+            FieldKind::TextArea => iced::Size::new(
+                attr("cols", 20.0) * char_width + 8.0 * px,
+                attr("rows", 2.0) * line_height + 8.0 * px,
+            ),
+            FieldKind::Button => iced::Size::new(
+                self.measure(&text, style) + 16.0 * px,
+                line_height + 8.0 * px,
+            ),
+        };
+        let rect = Rectangle::new(
+            Point::new(self.left, self.y + 2.0 * px),
+            iced::Size::new(size.width.min(self.available_width()), size.height),
+        );
+
+        self.push_fill(rect, FIELD_BORDER);
+        self.push_fill(
+            Rectangle::new(
+                Point::new(rect.x + px, rect.y + px),
+                iced::Size::new(rect.width - 2.0 * px, rect.height - 2.0 * px),
+            ),
+            if kind == FieldKind::Button {
+                FIELD_BUTTON
+            } else {
+                WHITE
+            },
+        );
+        match kind {
+            FieldKind::Checkbox => {
+                if value.checked {
+                    self.push_fill(
+                        Rectangle::new(
+                            Point::new(rect.x + 3.0 * px, rect.y + 3.0 * px),
+                            iced::Size::new(rect.width - 6.0 * px, rect.height - 6.0 * px),
+                        ),
+                        style.color,
+                    )
+                }
+            }
+            _ => {
+                let inner = Rectangle::new(
+                    Point::new(rect.x + 4.0 * px, rect.y + 4.0 * px),
+                    iced::Size::new(rect.width - 8.0 * px, rect.height - 8.0 * px),
+                );
+                let mut buffer = self.buffer(inner.width, style);
+                let attrs = attrs(style, 0);
+                buffer.set_text(self.font_system, &text, attrs, Shaping::Advanced);
+                if kind == FieldKind::Button {
+                    set_align(&mut buffer, TextAlign::Center);
+                }
+                buffer.shape_until_scroll(self.font_system, false);
+                self.page.paint.push(Paint::Text {
+                    buffer: Box::new(buffer),
+                    origin: inner.position(),
+                    clip: Some(inner),
+                });
+            }
+        }
+
+        self.page.fields.push(Field { kind, rect });
+        self.page.focusables.push(Focusable::Field(index));
+        self.y = rect.y + rect.height + 2.0 * px;
+        self.last_was_space = true;
+    }
+
+    /// Lays out the pending inline content as a paragraph
+    fn flush_inline(&mut self, block: &Style) {
+        let spans = std::mem::take(&mut self.spans);
+        self.last_was_space = true;
+        if spans
+            .iter()
+            .all(|span| span.text.trim().is_empty() && !span.text.contains('\n'))
+        {
+            return;
+        }
+        self.margin(0.0);
+
+        let mut buffer = self.buffer(self.available_width(), block);
+        buffer.set_rich_text(
+            self.font_system,
+            spans
+                .iter()
+                .enumerate()
+                .map(|(index, span)| (span.text.as_str(), attrs(&span.style, index + 1))),
+            attrs(block, 0),
+            Shaping::Advanced,
+        );
+        set_align(&mut buffer, block.text_align);
+        buffer.shape_until_scroll(self.font_system, false);
+
+        let origin = Point::new(self.left, self.y);
+        let mut height = 0.0;
+        for run in buffer.layout_runs() {
+            height = run.line_top + run.line_height;
+            for glyph in run.glyphs.iter() {
+                let Some(span) = glyph
+                    .metadata
+                    .checked_sub(1)
+                    .and_then(|index| spans.get(index))
+                else {
+                    continue;
+                };
+                let rect = Rectangle::new(
+                    Point::new(origin.x + glyph.x, origin.y + run.line_top),
+                    iced::Size::new(glyph.w, run.line_height),
+                );
+                if let Some(link) = span.link.and_then(|link| self.page.links.get_mut(link)) {
+                    link.rects.push(rect);
+                }
+                if span.style.underline {
+                    let thickness = (span.style.font_size / 14.0).max(1.0);
+                    self.page.paint.push(Paint::Fill {
+                        rect: Rectangle::new(
+                            Point::new(rect.x, origin.y + run.line_y + thickness),
+                            iced::Size::new(glyph.w, thickness),
+                        ),
+                        color: span.style.color,
+                    });
+                }
+            }
+        }
+
+        self.page.paint.push(Paint::Text {
+            buffer: Box::new(buffer),
+            origin,
+            clip: None,
+        });
+        self.y += height;
+    }
+
+    fn buffer(&mut self, width: f32, style: &Style) -> Buffer {
+        let mut buffer = Buffer::new(
+            self.font_system,
+            Metrics::new(style.font_size, style.font_size * LINE_HEIGHT),
+        );
+        buffer.set_size(self.font_system, Some(width.max(1.0)), None);
+        buffer
+    }
+
+    fn measure(&mut self, text: &str, style: &Style) -> f32 {
+        let mut buffer = self.buffer(f32::MAX, style);
+        buffer.set_text(self.font_system, text, attrs(style, 0), Shaping::Advanced);
+        buffer.shape_until_scroll(self.font_system, false);
+        buffer
+            .layout_runs()
+            .map(|run| run.line_w)
+            .fold(0.0, f32::max)
+    }
+
+    /// Applies the pending collapsed margin plus `extra`
+    fn margin(&mut self, extra: f32) {
+        self.y += self.pending_margin + extra;
+        self.pending_margin = 0.0;
+    }
+
+    fn push_fill(&mut self, rect: Rectangle, color: Color) {
+        self.page.paint.push(Paint::Fill { rect, color });
+    }
+
+    fn available_width(&self) -> f32 {
+        (self.width - self.left - self.right).max(1.0)
+    }
+}
+
+fn attrs(style: &Style, metadata: usize) -> Attrs<'static> {
+    let [r, g, b, a] = style.color;
+    Attrs::new()
+        .family(if style.monospace {
+            Family::Monospace
+        } else {
+            Family::SansSerif
+        })
+        .weight(if style.bold {
+            Weight::BOLD
+        } else {
+            Weight::NORMAL
+        })
+        .style(if style.italic {
+            cosmic_text::Style::Italic
+        } else {
+            cosmic_text::Style::Normal
+        })
+        .color(cosmic_text::Color::rgba(r, g, b, a))
+        .metrics(Metrics::new(style.font_size, style.font_size * LINE_HEIGHT))
+        .metadata(metadata)
+}
+
+fn set_align(buffer: &mut Buffer, align: TextAlign) {
+    let align = match align {
+        TextAlign::Left => None,
+        TextAlign::Center => Some(Align::Center),
+        TextAlign::Right => Some(Align::Right),
+    };
+    for line in buffer.lines.iter_mut() {
+        line.set_align(align);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lays out `html` at `width` css pixels, with the css of its `<style>` elements
+    fn layout(html: &str, width: f32) -> Page {
+        let document = Html::parse_document(html);
+        let css: String = Selector::parse("style")
+            .map(|selector| document.select(&selector).flat_map(|s| s.text()).collect())
+            .unwrap_or_default();
+        Page::layout(
+            &document,
+            &Stylesheet::parse(&css),
+            width,
+            1.0,
+            &mut FontSystem::new(),
+            &mut Vec::new(),
+        )
+    }
+
+    fn find(page: &Page, query: &str) -> Rectangle {
+        let matches = page.find(query, true);
+        assert_eq!(matches.len(), 1, "{query} should be on the page once");
+        matches[0]
+    }
+
+    #[test]
+    fn blocks_stack_with_collapsed_margins() {
+        let page = layout("<p>One</p><p>Two</p>", 400.0);
+        let (one, two) = (find(&page, "One"), find(&page, "Two"));
+
+        assert_eq!(one.x, two.x);
+        // the paragraphs' 1em margins collapse into one
+        assert_eq!(two.y - (one.y + one.height), 16.0);
+        assert!(page.height >= two.y + two.height);
+    }
+
+    #[test]
+    fn inline_content_shares_lines_until_it_wraps() {
+        let page = layout("<p>Hello <b>bold</b> <a href='#'>link</a></p>", 400.0);
+        let hello = find(&page, "Hello");
+        assert_eq!(find(&page, "bold").y, hello.y);
+        assert_eq!(find(&page, "link").y, hello.y);
+        assert!(find(&page, "link").x > find(&page, "bold").x);
+
+        let page = layout("<p>alpha beta gamma delta</p>", 80.0);
+        assert!(find(&page, "delta").y > find(&page, "alpha").y);
+    }
+
+    #[test]
+    fn links_are_hit_where_their_text_is() {
+        let page = layout(
+            "<p>Read <a href='/docs' target='_blank'>the docs</a> now</p>",
+            400.0,
+        );
+        assert_eq!(page.links.len(), 1);
+        assert_eq!(page.links[0].href, "/docs");
+        assert!(page.links[0].new_window);
+        assert_eq!(page.focusables, [Focusable::Link(0)]);
+
+        assert_eq!(page.link_at(find(&page, "docs").center()), Some(0));
+        assert_eq!(page.link_at(find(&page, "Read").center()), None);
+        assert_eq!(page.link_at(find(&page, "now").center()), None);
+    }
+
+    #[test]
+    fn anchors_are_where_their_element_starts() {
+        let page = layout(
+            "<p>Intro</p><h2 id='usage'>Usage</h2><p><a name='end'>End</a></p>",
+            400.0,
+        );
+        assert_eq!(page.anchor("usage"), Some(find(&page, "Usage").y));
+        assert_eq!(page.anchor("end"), Some(find(&page, "End").y));
+        assert_eq!(page.anchor("missing"), None);
+    }
+
+    #[test]
+    fn find_matches_every_occurrence() {
+        let page = layout("<p>Iced and iced</p><p>ICED</p>", 400.0);
+        assert_eq!(page.find("iced", false).len(), 3);
+        assert_eq!(page.find("iced", true).len(), 1);
+        assert_eq!(page.find("", false).len(), 0);
+
+        let matches = page.find("iced", false);
+        assert!(matches[0].x < matches[1].x);
+        assert!(matches[1].y < matches[2].y);
+    }
+
+    #[test]
+    fn find_compares_text_as_lowercase() {
+        assert_eq!(find_text("Hello World", "world", false), Some((6, 5)));
+        assert_eq!(find_text("Hello World", "world", true), None);
+        // 'Ä' takes as many bytes as 'ä'
+        assert_eq!(find_text("ÄPFEL", "äpfel", false), Some((0, 6)));
+        assert_eq!(find_text("Hello", "hello world", false), None);
+    }
+
+    #[test]
+    fn unsupported_markup_is_left_out() {
+        let page = layout(
+            "<head><title>Title</title></head>
+             <script>let hidden = 1;</script>
+             <style>@media print { p { display: none } } .gone { display: none }</style>
+             <p class='gone'>gone</p>
+             <p><img src='logo.png' alt='Logo'><video>fallback</video><custom-tag>kept</custom-tag></p>",
+            400.0,
+        );
+        assert!(page.find("hidden", true).is_empty());
+        assert!(page.find("Title", true).is_empty());
+        assert!(page.find("gone", true).is_empty());
+        find(&page, "Logo");
+        find(&page, "fallback");
+        find(&page, "kept");
+    }
+
+    #[test]
+    fn form_fields_keep_their_values() {
+        let html = "<input type='hidden' value='secret'><input value='name'>
+                    <input type='checkbox' checked><button>Send</button>";
+        let mut values = Vec::new();
+        let page = Page::layout(
+            &Html::parse_document(html),
+            &Stylesheet::default(),
+            400.0,
+            1.0,
+            &mut FontSystem::new(),
+            &mut values,
+        );
+
+        let kinds: Vec<_> = page.fields.iter().map(|field| field.kind).collect();
+        assert_eq!(
+            kinds,
+            [FieldKind::Text, FieldKind::Checkbox, FieldKind::Button]
+        );
+        assert_eq!(values[0].text, "name");
+        assert!(values[1].checked);
+        assert_eq!(values[2].text, "Send");
+        assert_eq!(page.field_at(page.fields[1].rect.center()), Some(1));
+    }
+}
@@ -0,0 +1,513 @@
+use scraper::{ElementRef, Selector};
+
+/// RGBA colour
+pub(super) type Color = [u8; 4];
+
+pub(super) const BLACK: Color = [0, 0, 0, 255];
+pub(super) const WHITE: Color = [255, 255, 255, 255];
+pub(super) const LINK_BLUE: Color = [0, 0, 238, 255];
+
+/// The font size everything else is relative to, like a browser's `medium`
+const ROOT_FONT_SIZE: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Display {
+    Block,
+    Inline,
+    ListItem,
+    None,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// The computed style of an element. Only the subset of CSS the engine can lay out is kept
+#[derive(Clone, Debug)]
+pub(super) struct Style {
+    pub display: Display,
+    pub color: Color,
+    pub background: Option<Color>,
+    pub font_size: f32,
+    pub bold: bool,
+    pub italic: bool,
+    pub monospace: bool,
+    pub underline: bool,
+    pub preformatted: bool,
+    pub text_align: TextAlign,
+    pub margin_top: f32,
+    pub margin_bottom: f32,
+    pub margin_left: f32,
+    pub padding: f32,
+    /// How many pixels a css pixel covers, every length is already multiplied by it
+    pub scale: f32,
+}
+
+impl Style {
+    pub fn root(scale: f32) -> Self {
+        Self {
+            display: Display::Block,
+            color: BLACK,
+            background: None,
+            font_size: ROOT_FONT_SIZE * scale,
+            bold: false,
+            italic: false,
+            monospace: false,
+            underline: false,
+            preformatted: false,
+            text_align: TextAlign::Left,
+            margin_top: 0.0,
+            margin_bottom: 0.0,
+            margin_left: 0.0,
+            padding: 0.0,
+            scale,
+        }
+    }
+
+    /// Keeps the inherited properties of `parent` and resets the rest
+    fn inherit(parent: &Style) -> Self {
+        Self {
+            display: Display::Inline,
+            background: None,
+            margin_top: 0.0,
+            margin_bottom: 0.0,
+            margin_left: 0.0,
+            padding: 0.0,
+            ..parent.clone()
+        }
+    }
+
+    /// Computes the style of `element` from the built in defaults, the page's stylesheet and
+    /// finally its `style` attribute
+    pub fn compute(element: &ElementRef, parent: &Style, stylesheet: &Stylesheet) -> Self {
+        let mut style = Self::inherit(parent);
+        style.apply_user_agent(element);
+
+        for (property, value) in stylesheet.declarations_for(element) {
+            style.apply(property, value, parent);
+        }
+        if let Some(inline) = element.value().attr("style") {
+            for (property, value) in parse_declarations(inline) {
+                style.apply(&property, &value, parent);
+            }
+        }
+        style
+    }
+
+    fn apply_user_agent(&mut self, element: &ElementRef) {
+        let em = self.font_size;
+        match element.value().name() {
+            "head" | "script" | "style" | "title" | "meta" | "link" | "noscript" | "template" => {
+                self.display = Display::None
+            }
+            "html" | "div" | "section" | "article" | "header" | "footer" | "nav" | "main"
+            | "aside" | "form" | "table" | "tr" | "dl" | "dt" | "figure" | "figcaption"
+            | "address" | "details" | "summary" | "fieldset" | "caption" | "tbody" | "thead"
+            | "tfoot" => self.display = Display::Block,
+            "body" => {
+                self.display = Display::Block;
+                self.padding = 8.0 * self.scale;
+            }
+            "p" => {
+                self.display = Display::Block;
+                self.margin_top = em;
+                self.margin_bottom = em;
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let (scale, margin) = match element.value().name() {
+                    "h1" => (2.0, 0.67),
+                    "h2" => (1.5, 0.83),
+                    "h3" => (1.17, 1.0),
+                    "h4" => (1.0, 1.33),
+                    "h5" => (0.83, 1.67),
+                    _ => (0.67, 2.33),
+                };
+                self.display = Display::Block;
+                self.bold = true;
+                self.font_size = em * scale;
+                self.margin_top = self.font_size * margin;
+                self.margin_bottom = self.font_size * margin;
+            }
+            "ul" | "ol" | "menu" => {
+                self.display = Display::Block;
+                self.margin_top = em;
+                self.margin_bottom = em;
+                self.margin_left = 40.0 * self.scale;
+            }
+            "li" => self.display = Display::ListItem,
+            "dd" => {
+                self.display = Display::Block;
+                self.margin_left = 40.0 * self.scale;
+            }
+            "blockquote" => {
+                self.display = Display::Block;
+                self.margin_top = em;
+                self.margin_bottom = em;
+                self.margin_left = 40.0 * self.scale;
+            }
+            "pre" => {
+                self.display = Display::Block;
+                self.monospace = true;
+                self.preformatted = true;
+                self.margin_top = em;
+                self.margin_bottom = em;
+            }
+            "hr" => {
+                self.display = Display::Block;
+                self.margin_top = em / 2.0;
+                self.margin_bottom = em / 2.0;
+            }
+            "center" => {
+                self.display = Display::Block;
+                self.text_align = TextAlign::Center;
+            }
+            "code" | "kbd" | "samp" | "tt" => self.monospace = true,
+            "b" | "strong" => self.bold = true,
+            "th" => {
+                self.bold = true;
+                self.text_align = TextAlign::Center;
+            }
+            "i" | "em" | "cite" | "var" | "dfn" => self.italic = true,
+            "u" | "ins" => self.underline = true,
+            "small" => self.font_size = em * 0.83,
+            "big" => self.font_size = em * 1.2,
+            "a" if element.value().attr("href").is_some() => {
+                self.color = LINK_BLUE;
+                self.underline = true;
+            }
+            _ => (),
+        }
+    }
+
+    fn apply(&mut self, property: &str, value: &str, parent: &Style) {
+        let value = value.trim_end_matches("!important").trim();
+        match property {
+            "display" => {
+                self.display = match value {
+                    "none" => Display::None,
+                    "block" | "flex" | "grid" | "table" | "table-row" => Display::Block,
+                    "list-item" => Display::ListItem,
+                    _ => Display::Inline,
+                }
+            }
+            "color" => {
+                if let Some(color) = parse_color(value) {
+                    self.color = color
+                }
+            }
+            "background" | "background-color" => {
+                self.background = value
+                    .split_whitespace()
+                    .find_map(parse_color)
+                    .filter(|color| color[3] != 0)
+            }
+            "font-size" => {
+                let size = match value {
+                    "xx-small" => Some(ROOT_FONT_SIZE * 0.6 * parent.scale),
+                    "x-small" => Some(ROOT_FONT_SIZE * 0.75 * parent.scale),
+                    "small" => Some(ROOT_FONT_SIZE * 0.89 * parent.scale),
+                    "medium" => Some(ROOT_FONT_SIZE * parent.scale),
+                    "large" => Some(ROOT_FONT_SIZE * 1.2 * parent.scale),
+                    "x-large" => Some(ROOT_FONT_SIZE * 1.5 * parent.scale),
+                    "xx-large" => Some(ROOT_FONT_SIZE * 2.0 * parent.scale),
+                    "smaller" => Some(parent.font_size / 1.2),
+                    "larger" => Some(parent.font_size * 1.2),
+                    _ => parse_length(value, parent.font_size, parent.font_size, parent.scale),
+                };
+                if let Some(size) = size.filter(|size| *size > 0.0) {
+                    self.font_size = size
+                }
+            }
+            "font-weight" => {
+                self.bold = match value {
+                    "bold" | "bolder" => true,
+                    "normal" | "lighter" => false,
+                    weight => weight.parse::<u16>().map(|w| w >= 600).unwrap_or(self.bold),
+                }
+            }
+            "font-style" => self.italic = value == "italic" || value == "oblique",
+            "font-family" => self.monospace = value.contains("monospace"),
+            "text-decoration" | "text-decoration-line" => {
+                self.underline = value.contains("underline")
+            }
+            "text-align" => {
+                self.text_align = match value {
+                    "center" => TextAlign::Center,
+                    "right" | "end" => TextAlign::Right,
+                    _ => TextAlign::Left,
+                }
+            }
+            "white-space" => self.preformatted = value.starts_with("pre"),
+            "margin" => {
+                let parts: Vec<&str> = value.split_whitespace().collect();
+                let vertical = parts.first().and_then(|v| self.length(v));
+                let bottom = parts.get(2).and_then(|v| self.length(v)).or(vertical);
+                let left = match parts.len() {
+                    1 => vertical,
+                    2 | 3 => parts.get(1).and_then(|v| self.length(v)),
+                    _ => parts.get(3).and_then(|v| self.length(v)),
+                };
+                self.margin_top = vertical.unwrap_or(self.margin_top);
+                self.margin_bottom = bottom.unwrap_or(self.margin_bottom);
+                self.margin_left = left.unwrap_or(self.margin_left);
+            }
+            "margin-top" => self.margin_top = self.length(value).unwrap_or(self.margin_top),
+            "margin-bottom" => {
+                self.margin_bottom = self.length(value).unwrap_or(self.margin_bottom)
+            }
+            "margin-left" => self.margin_left = self.length(value).unwrap_or(self.margin_left),
+            "padding" => {
+                self.padding = value
+                    .split_whitespace()
+                    .next()
+                    .and_then(|v| self.length(v))
+                    .unwrap_or(self.padding)
+            }
+            _ => (),
+        }
+    }
+
+    fn length(&self, value: &str) -> Option<f32> {
+        parse_length(value, self.font_size, 0.0, self.scale).map(|length| length.max(0.0))
+    }
+}
+
+/// Parses a CSS length into pixels. Percentages are relative to `percent_of`,
+/// and a css pixel covers `px` pixels
+fn parse_length(value: &str, em: f32, percent_of: f32, px: f32) -> Option<f32> {
+    let value = value.trim();
+    let (number, scale) = if let Some(number) = value.strip_suffix("px") {
+        (number, px)
+    } else if let Some(number) = value.strip_suffix("rem") {
+        (number, ROOT_FONT_SIZE * px)
+    } else if let Some(number) = value.strip_suffix("em") {
+        (number, em)
+    } else if let Some(number) = value.strip_suffix("pt") {
+        (number, 4.0 / 3.0 * px)
+    } else if let Some(number) = value.strip_suffix('%') {
+        (number, percent_of / 100.0)
+    } else if value == "0" || value == "auto" {
+        return Some(0.0);
+    } else {
+        return None;
+    };
+    number
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .map(|number| number * scale)
+}
+
+pub(super) fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |i: usize, len: usize| {
+            u8::from_str_radix(hex.get(i * len..i * len + len)?, 16)
+                .ok()
+                .map(|v| if len == 1 { v * 17 } else { v })
+        };
+        return match hex.len() {
+            3 => Some([channel(0, 1)?, channel(1, 1)?, channel(2, 1)?, 255]),
+            6 => Some([channel(0, 2)?, channel(1, 2)?, channel(2, 2)?, 255]),
+            8 => Some([
+                channel(0, 2)?,
+                channel(1, 2)?,
+                channel(2, 2)?,
+                channel(3, 2)?,
+            ]),
+            _ => None,
+        };
+    }
+    if let Some(args) = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))
+        .and_then(|args| args.strip_suffix(')'))
+    {
+        let args: Vec<&str> = args
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .collect();
+        let channel = |arg: &str| arg.parse::<f32>().ok().map(|v| v.clamp(0.0, 255.0) as u8);
+        let alpha = match args.get(3) {
+            Some(alpha) => (alpha.parse::<f32>().ok()?.clamp(0.0, 1.0) * 255.0) as u8,
+            None => 255,
+        };
+        return Some([
+            channel(args.first()?)?,
+            channel(args.get(1)?)?,
+            channel(args.get(2)?)?,
+            alpha,
+        ]);
+    }
+    Some(match value.as_str() {
+        "black" => BLACK,
+        "white" => WHITE,
+        "red" => [255, 0, 0, 255],
+        "green" => [0, 128, 0, 255],
+        "blue" => [0, 0, 255, 255],
+        "gray" | "grey" => [128, 128, 128, 255],
+        "lightgray" | "lightgrey" => [211, 211, 211, 255],
+        "darkgray" | "darkgrey" => [169, 169, 169, 255],
+        "silver" => [192, 192, 192, 255],
+        "maroon" => [128, 0, 0, 255],
+        "purple" => [128, 0, 128, 255],
+        "fuchsia" | "magenta" => [255, 0, 255, 255],
+        "lime" => [0, 255, 0, 255],
+        "olive" => [128, 128, 0, 255],
+        "yellow" => [255, 255, 0, 255],
+        "navy" => [0, 0, 128, 255],
+        "teal" => [0, 128, 128, 255],
+        "aqua" | "cyan" => [0, 255, 255, 255],
+        "orange" => [255, 165, 0, 255],
+        "transparent" => [0, 0, 0, 0],
+        _ => return None,
+    })
+}
+
+/// Splits `color: red; margin: 0` into its property/value pairs
+fn parse_declarations(css: &str) -> Vec<(String, String)> {
+    css.split(';')
+        .filter_map(|declaration| declaration.split_once(':'))
+        .map(|(property, value)| {
+            (
+                property.trim().to_ascii_lowercase(),
+                value.trim().to_string(),
+            )
+        })
+        .filter(|(property, value)| !property.is_empty() && !value.is_empty())
+        .collect()
+}
+
+/// The rules of every `<style>` element in a page
+///
+/// Rules are applied in source order, specificity is not taken into account
+#[derive(Default)]
+pub(super) struct Stylesheet {
+    rules: Vec<(Selector, Vec<(String, String)>)>,
+}
+
+impl Stylesheet {
+    pub fn parse(css: &str) -> Self {
+        let mut rules = Vec::new();
+        let stripped = strip_comments(css);
+        let mut css = stripped.as_str();
+
+        while let Some(open) = css.find('{') {
+            let prelude = css[..open].trim();
+            let Some(close) = matching_brace(&css[open..]).map(|close| open + close) else {
+                break;
+            };
+            // at-rules like @media are skipped entirely
+            if !prelude.starts_with('@') {
+                if let Ok(selector) = Selector::parse(prelude) {
+                    rules.push((selector, parse_declarations(&css[open + 1..close])));
+                }
+            }
+            css = &css[close + 1..];
+        }
+
+        Self { rules }
+    }
+
+    fn declarations_for<'a>(
+        &'a self,
+        element: &'a ElementRef,
+    ) -> impl Iterator<Item = (&'a str, &'a str)> {
+        self.rules
+            .iter()
+            .filter(|(selector, _)| selector.matches(element))
+            .flat_map(|(_, declarations)| declarations.iter())
+            .map(|(property, value)| (property.as_str(), value.as_str()))
+    }
+}
+
+fn strip_comments(css: &str) -> String {
+    let mut stripped = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("/*") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("*/") {
+            Some(end) => &rest[start + 2 + end + 2..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Finds the `}` closing the `{` that `css` starts with
+fn matching_brace(css: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in css.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    /// The style of the first element matching `selector`, in a document styled by `css`
+    fn style(html: &str, css: &str, selector: &str) -> Style {
+        let document = Html::parse_document(html);
+        let stylesheet = Stylesheet::parse(css);
+        let selector = Selector::parse(selector).unwrap();
+        let element = document.select(&selector).next().unwrap();
+        Style::compute(&element, &Style::root(1.0), &stylesheet)
+    }
+
+    #[test]
+    fn colors_in_every_notation() {
+        assert_eq!(parse_color("#f00"), Some([255, 0, 0, 255]));
+        assert_eq!(parse_color("#00ff0080"), Some([0, 255, 0, 128]));
+        assert_eq!(parse_color("rgb(1, 2, 3)"), Some([1, 2, 3, 255]));
+        assert_eq!(parse_color("rgba(1 2 3 / 0)"), Some([1, 2, 3, 0]));
+        assert_eq!(parse_color(" Navy "), Some([0, 0, 128, 255]));
+        assert_eq!(parse_color("#ff"), None);
+        assert_eq!(parse_color("currentcolor"), None);
+    }
+
+    #[test]
+    fn lengths_in_every_unit() {
+        assert_eq!(parse_length("10px", 16.0, 100.0, 2.0), Some(20.0));
+        assert_eq!(parse_length("2em", 16.0, 100.0, 2.0), Some(32.0));
+        assert_eq!(parse_length("50%", 16.0, 300.0, 2.0), Some(150.0));
+        assert_eq!(parse_length("auto", 16.0, 100.0, 2.0), Some(0.0));
+        assert_eq!(parse_length("calc(1px)", 16.0, 100.0, 2.0), None);
+    }
+
+    #[test]
+    fn rules_apply_in_order_before_inline_styles() {
+        let css = "/* p { color: red } */ p { color: blue } .note { color: green }";
+        let html = "<p class='note'>a</p><p class='note' style='color: black'>b</p>";
+        assert_eq!(style(html, css, "p").color, [0, 128, 0, 255]);
+        assert_eq!(style(html, css, "p[style]").color, BLACK);
+    }
+
+    #[test]
+    fn unsupported_css_is_skipped() {
+        let css = "@media print { p { display: none } } p { float: left; color: red }";
+        let p = style("<p>a</p>", css, "p");
+        assert_eq!(p.display, Display::Block);
+        assert_eq!(p.color, [255, 0, 0, 255]);
+
+        assert_eq!(
+            style("<script></script>", "", "script").display,
+            Display::None
+        );
+    }
+}
@@ -0,0 +1,844 @@
+use iced::futures::channel::mpsc::{self as futures_mpsc, UnboundedReceiver, UnboundedSender};
+use iced::futures::{lock, stream, StreamExt};
+use iced::keyboard;
+use iced::mouse::{self, Interaction};
+use iced::{Point, Size, Subscription};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::{
+    BrowsingData, DialogAnswer, DialogId, Engine, PageType, RequestId, Response, Session,
+    ViewEvent, ViewId,
+};
+use crate::{Assets, Error, ImageInfo};
+
+// How often the worker drives a busy engine when no commands arrive
+const TICK: Duration = Duration::from_millis(10);
+// How often the worker drives an idle engine, so timers in its pages still run
+const IDLE_TICK: Duration = Duration::from_secs(1);
+
+// Gives every host its own subscription
+static NEXT_HOST_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Work sent from the ui thread to the engine's thread
+enum Command {
+    NewView(ViewId, Size<u32>, Option<PageType>, Session),
+    RemoveView(ViewId),
+    Render(ViewId),
+    Resize(ViewId, Size<u32>),
+    SetScaleFactor(ViewId, f32),
+    SetZoom(ViewId, f32),
+    CaptureFullPage(ViewId),
+    Focus(ViewId),
+    Unfocus(ViewId),
+    Keyboard(ViewId, keyboard::Event),
+    Mouse(ViewId, Point, mouse::Event),
+    Scroll(ViewId, mouse::ScrollDelta),
+    Goto(ViewId, PageType),
+    Refresh(ViewId),
+    GoForward(ViewId),
+    GoBack(ViewId),
+    EvaluateScript(ViewId, String),
+    PostMessage(ViewId, String),
+    Find(ViewId, String, bool, bool),
+    StopFinding(ViewId),
+    Respond(ViewId, RequestId, Response),
+    AnswerDialog(ViewId, DialogId, DialogAnswer),
+    SetAssets(Assets),
+    SetScheme(String),
+    InterceptNavigation(bool),
+    ClearData(Option<Session>, BrowsingData),
+}
+
+impl Command {
+    /// The view the command is for, if it is not for the whole engine
+    fn view_id(&self) -> Option<ViewId> {
+        Some(match self {
+            Command::NewView(id, ..)
+            | Command::RemoveView(id)
+            | Command::Render(id)
+            | Command::Resize(id, _)
+            | Command::SetScaleFactor(id, _)
+            | Command::SetZoom(id, _)
+            | Command::CaptureFullPage(id)
+            | Command::Focus(id)
+            | Command::Unfocus(id)
+            | Command::Keyboard(id, _)
+            | Command::Mouse(id, ..)
+            | Command::Scroll(id, _)
+            | Command::Goto(id, _)
+            | Command::Refresh(id)
+            | Command::GoForward(id)
+            | Command::GoBack(id)
+            | Command::EvaluateScript(id, _)
+            | Command::PostMessage(id, _)
+            | Command::Find(id, ..)
+            | Command::StopFinding(id)
+            | Command::Respond(id, ..)
+            | Command::AnswerDialog(id, ..) => *id,
+            Command::SetAssets(_)
+            | Command::SetScheme(_)
+            | Command::InterceptNavigation(_)
+            | Command::ClearData(..) => return None,
+        })
+    }
+}
+
+/// What the worker last published for a view
+#[derive(Default)]
+struct Published {
+    // only set while a new frame is waiting to be picked up
+    frame: Option<ImageInfo>,
+    url: String,
+    title: String,
+    cursor: Interaction,
+}
+
+/// Results handed from the engine's thread back to the ui thread
+#[derive(Default)]
+struct Snapshot {
+    views: HashMap<ViewId, Published>,
+    events: Vec<(ViewId, ViewEvent)>,
+    errors: Vec<(ViewId, Error)>,
+    cleared_data: Vec<Result<(), Error>>,
+    // whether the engine was busy as of the worker's last tick
+    needs_update: bool,
+    // set once something changed, cleared when the ui thread picks it up
+    changed: bool,
+}
+
+/// The ui thread's copy of a view
+struct View {
+    id: ViewId,
+    size: Size<u32>,
+    zoom: f32,
+    focused: bool,
+    frame: ImageInfo,
+    url: String,
+    title: String,
+    cursor: Interaction,
+    session: Session,
+}
+
+/// Runs another Engine on its own thread, so page loads and renders never block iced
+///
+/// Every call is forwarded to the worker thread over a channel and returns immediately.
+/// The worker drives the engine about once a frame while it is busy and once a second while it is idle,
+/// and publishes finished frames, urls, titles, cursors and events,
+/// which become visible on the next [`Engine::update`]. Its [`Engine::subscription`]
+/// wakes the webview up when there is something new, instead of polling.
+///
+/// Errors from the worker, like a page failing to load, are returned by the next
+/// [`Engine::request_render`] of that view.
+///
+/// ```rust
+/// # #[cfg(feature = "ultralight")]
+/// # mod example {
+/// # #[derive(Clone)]
+/// # enum Message { WebView(iced_webview::Action) }
+/// use iced_webview::{Threaded, Ultralight, WebView};
+///
+/// struct State {
+///     webview: WebView<Threaded<Ultralight>, Message>,
+/// }
+///
+/// fn subscription(state: &State) -> iced::Subscription<Message> {
+///     state.webview.subscription().map(Message::WebView)
+/// }
+/// # }
+/// ```
+pub struct Threaded<E: Engine> {
+    id: usize,
+    commands: Option<mpsc::Sender<Command>>,
+    worker: Option<JoinHandle<()>>,
+    snapshot: Arc<Mutex<Snapshot>>,
+    // shared by every subscription, so a new one picks up where a dropped one left off
+    updates: Arc<lock::Mutex<UnboundedReceiver<()>>>,
+    views: Vec<View>,
+    next_id: ViewId,
+    _engine: std::marker::PhantomData<fn() -> E>,
+}
+
+impl<E: Engine + Default + 'static> Default for Threaded<E> {
+    fn default() -> Self {
+        Self::new(E::default)
+    }
+}
+
+impl<E: Engine + 'static> Threaded<E> {
+    /// Starts a worker thread and creates the engine on it
+    ///
+    /// The engine itself does not need to be `Send`, only the function creating it
+    pub fn new(create: impl FnOnce() -> E + Send + 'static) -> Self {
+        let (commands, receiver) = mpsc::channel();
+        let (notify, updates) = futures_mpsc::unbounded();
+        let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+
+        let worker_snapshot = snapshot.clone();
+        let worker = thread::Builder::new()
+            .name("iced_webview engine".to_string())
+            .spawn(move || Worker::new(create(), worker_snapshot, notify).run(receiver))
+            .expect("Failed to spawn the engine thread");
+
+        Self {
+            id: NEXT_HOST_ID.fetch_add(1, Ordering::Relaxed),
+            commands: Some(commands),
+            worker: Some(worker),
+            snapshot,
+            updates: Arc::new(lock::Mutex::new(updates)),
+            views: Vec::new(),
+            next_id: 0,
+            _engine: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<E: Engine> Threaded<E> {
+    fn send(&self, command: Command) {
+        // the worker only stops when the host is dropped, or if the engine panicked
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(command);
+        }
+    }
+
+    fn get_view(&self, id: ViewId) -> Result<&View, Error> {
+        self.views
+            .iter()
+            .find(|view| view.id == id)
+            .ok_or(Error::UnknownView(id))
+    }
+
+    fn get_view_mut(&mut self, id: ViewId) -> Result<&mut View, Error> {
+        self.views
+            .iter_mut()
+            .find(|view| view.id == id)
+            .ok_or(Error::UnknownView(id))
+    }
+
+    /// Picks up everything the worker published since the last sync
+    fn sync(&mut self) {
+        let Ok(mut snapshot) = self.snapshot.lock() else {
+            return;
+        };
+        if !snapshot.changed {
+            return;
+        }
+        snapshot.changed = false;
+
+        for view in self.views.iter_mut() {
+            if let Some(published) = snapshot.views.get_mut(&view.id) {
+                if let Some(frame) = published.frame.take() {
+                    view.frame = frame;
+                }
+                view.url.clone_from(&published.url);
+                view.title.clone_from(&published.title);
+                view.cursor = published.cursor;
+            }
+        }
+    }
+
+    /// Takes the oldest error the worker ran into for a view
+    fn take_error(&mut self, id: ViewId) -> Result<(), Error> {
+        let Ok(mut snapshot) = self.snapshot.lock() else {
+            return Ok(());
+        };
+        match snapshot.errors.iter().position(|(view, _)| *view == id) {
+            Some(index) => Err(snapshot.errors.remove(index).1),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends a command for a view that must exist
+    fn send_to(&self, id: ViewId, command: Command) -> Result<(), Error> {
+        self.get_view(id)?;
+        self.send(command);
+        Ok(())
+    }
+}
+
+impl<E: Engine> Drop for Threaded<E> {
+    fn drop(&mut self) {
+        // closing the channel stops the worker
+        self.commands.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<E: Engine> Engine for Threaded<E> {
+    fn update(&mut self) {
+        self.sync();
+    }
+
+    fn take_events(&mut self) -> Vec<(ViewId, ViewEvent)> {
+        let Ok(mut snapshot) = self.snapshot.lock() else {
+            return Vec::new();
+        };
+        let mut events = std::mem::take(&mut snapshot.events);
+        // the worker may still report events for views closed in the meantime
+        events.retain(|(id, _)| self.views.iter().any(|view| view.id == *id));
+        events
+    }
+
+    /// Whether the engine was busy when the worker last checked, so the webview keeps syncing quickly
+    fn needs_update(&self) -> bool {
+        self.snapshot
+            .lock()
+            .is_ok_and(|snapshot| snapshot.needs_update)
+    }
+
+    /// Produces a message every time the worker has published new frames, urls, titles or cursors
+    ///
+    /// Subscriptions share the worker's wake ups, so subscribing again after dropping it keeps working,
+    /// while subscribing twice at once wakes only one of them each time
+    fn subscription(&self) -> Subscription<()> {
+        let updates = self.updates.clone();
+        Subscription::run_with_id(
+            self.id,
+            stream::unfold(updates, |updates| async move {
+                let update = updates.lock().await.next().await;
+                update.map(|update| (update, updates))
+            }),
+        )
+    }
+
+    fn render(&mut self) {
+        self.sync();
+    }
+
+    fn request_render(&mut self, id: ViewId) -> Result<(), Error> {
+        self.send_to(id, Command::Render(id))?;
+        self.sync();
+        self.take_error(id)
+    }
+
+    fn new_view(
+        &mut self,
+        size: Size<u32>,
+        content: Option<PageType>,
+        session: Session,
+    ) -> Result<ViewId, Error> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.views.push(View {
+            id,
+            size,
+            zoom: 1.0,
+            focused: false,
+            frame: ImageInfo::blank(size.width, size.height),
+            url: String::new(),
+            title: String::new(),
+            cursor: Interaction::Idle,
+            session: session.clone(),
+        });
+        self.send(Command::NewView(id, size, content, session));
+        Ok(id)
+    }
+
+    fn remove_view(&mut self, id: ViewId) -> Result<(), Error> {
+        self.send_to(id, Command::RemoveView(id))?;
+        self.views.retain(|view| view.id != id);
+        // errors for a closed view have nowhere to go
+        if let Ok(mut snapshot) = self.snapshot.lock() {
+            snapshot.errors.retain(|(view, _)| *view != id);
+        }
+        Ok(())
+    }
+
+    fn focus(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.focused = true;
+        self.send(Command::Focus(id));
+        Ok(())
+    }
+
+    fn unfocus(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.focused = false;
+        self.send(Command::Unfocus(id));
+        Ok(())
+    }
+
+    fn has_focus(&self, id: ViewId) -> Result<bool, Error> {
+        Ok(self.get_view(id)?.focused)
+    }
+
+    fn resize(&mut self, id: ViewId, size: Size<u32>) -> Result<(), Error> {
+        self.get_view_mut(id)?.size = size;
+        self.send(Command::Resize(id, size));
+        Ok(())
+    }
+
+    fn set_scale_factor(&mut self, id: ViewId, scale_factor: f32) -> Result<(), Error> {
+        self.send_to(id, Command::SetScaleFactor(id, scale_factor))
+    }
+
+    fn set_zoom(&mut self, id: ViewId, zoom: f32) -> Result<(), Error> {
+        self.get_view_mut(id)?.zoom = zoom;
+        self.send(Command::SetZoom(id, zoom));
+        Ok(())
+    }
+
+    fn handle_keyboard_event(&mut self, id: ViewId, event: keyboard::Event) -> Result<(), Error> {
+        self.send_to(id, Command::Keyboard(id, event))
+    }
+
+    fn handle_mouse_event(
+        &mut self,
+        id: ViewId,
+        point: Point,
+        event: mouse::Event,
+    ) -> Result<(), Error> {
+        self.send_to(id, Command::Mouse(id, point, event))
+    }
+
+    fn scroll(&mut self, id: ViewId, delta: mouse::ScrollDelta) -> Result<(), Error> {
+        self.send_to(id, Command::Scroll(id, delta))
+    }
+
+    fn set_assets(&mut self, assets: Assets) {
+        self.send(Command::SetAssets(assets));
+    }
+
+    fn set_scheme(&mut self, scheme: &str) {
+        self.send(Command::SetScheme(scheme.to_string()));
+    }
+
+    fn respond(&mut self, id: ViewId, request: RequestId, response: Response) -> Result<(), Error> {
+        self.send_to(id, Command::Respond(id, request, response))
+    }
+
+    fn answer_dialog(
+        &mut self,
+        id: ViewId,
+        dialog: DialogId,
+        answer: DialogAnswer,
+    ) -> Result<(), Error> {
+        self.send_to(id, Command::AnswerDialog(id, dialog, answer))
+    }
+
+    fn intercept_navigation(&mut self, intercept: bool) {
+        self.send(Command::InterceptNavigation(intercept));
+    }
+
+    /// The outcome is published by the worker once the engine has cleared the data
+    fn clear_data(&mut self, session: Option<Session>, data: BrowsingData) {
+        self.send(Command::ClearData(session, data));
+    }
+
+    fn take_cleared_data(&mut self) -> Vec<Result<(), Error>> {
+        self.snapshot
+            .lock()
+            .map(|mut snapshot| std::mem::take(&mut snapshot.cleared_data))
+            .unwrap_or_default()
+    }
+
+    fn goto(&mut self, id: ViewId, page_type: PageType) -> Result<(), Error> {
+        self.send_to(id, Command::Goto(id, page_type))
+    }
+
+    fn refresh(&mut self, id: ViewId) -> Result<(), Error> {
+        self.send_to(id, Command::Refresh(id))
+    }
+
+    fn go_forward(&mut self, id: ViewId) -> Result<(), Error> {
+        self.send_to(id, Command::GoForward(id))
+    }
+
+    fn go_back(&mut self, id: ViewId) -> Result<(), Error> {
+        self.send_to(id, Command::GoBack(id))
+    }
+
+    fn evaluate_script(&mut self, id: ViewId, script: &str) -> Result<(), Error> {
+        self.send_to(id, Command::EvaluateScript(id, script.to_string()))
+    }
+
+    fn post_message(&mut self, id: ViewId, message: &str) -> Result<(), Error> {
+        self.send_to(id, Command::PostMessage(id, message.to_string()))
+    }
+
+    fn find(
+        &mut self,
+        id: ViewId,
+        query: &str,
+        case_sensitive: bool,
+        forward: bool,
+    ) -> Result<(), Error> {
+        let command = Command::Find(id, query.to_string(), case_sensitive, forward);
+        self.send_to(id, command)
+    }
+
+    fn stop_finding(&mut self, id: ViewId) -> Result<(), Error> {
+        self.send_to(id, Command::StopFinding(id))
+    }
+
+    fn get_url(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self.get_view(id)?.url.clone())
+    }
+
+    fn get_title(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self.get_view(id)?.title.clone())
+    }
+
+    fn get_session(&self, id: ViewId) -> Result<Session, Error> {
+        Ok(self.get_view(id)?.session.clone())
+    }
+
+    fn get_cursor(&self, id: ViewId) -> Result<Interaction, Error> {
+        Ok(self.get_view(id)?.cursor)
+    }
+
+    fn get_zoom(&self, id: ViewId) -> Result<f32, Error> {
+        Ok(self.get_view(id)?.zoom)
+    }
+
+    fn get_view(&self, id: ViewId) -> Result<&ImageInfo, Error> {
+        Ok(&self.get_view(id)?.frame)
+    }
+
+    fn capture_full_page(&mut self, id: ViewId) -> Result<(), Error> {
+        self.send_to(id, Command::CaptureFullPage(id))
+    }
+}
+
+/// Owns the engine on its own thread
+struct Worker<E: Engine> {
+    engine: E,
+    // the host's ids, mapped to the engine's own
+    ids: HashMap<ViewId, ViewId>,
+    last_frames: HashMap<ViewId, ImageInfo>,
+    snapshot: Arc<Mutex<Snapshot>>,
+    notify: UnboundedSender<()>,
+}
+
+impl<E: Engine> Worker<E> {
+    fn new(engine: E, snapshot: Arc<Mutex<Snapshot>>, notify: UnboundedSender<()>) -> Self {
+        Self {
+            engine,
+            ids: HashMap::new(),
+            last_frames: HashMap::new(),
+            snapshot,
+            notify,
+        }
+    }
+
+    fn run(mut self, commands: mpsc::Receiver<Command>) {
+        loop {
+            let tick = if self.engine.needs_update() {
+                TICK
+            } else {
+                IDLE_TICK
+            };
+            let handled = match commands.recv_timeout(tick) {
+                Ok(command) => {
+                    self.handle(command);
+                    // catch up on everything that queued while the engine was busy
+                    while let Ok(command) = commands.try_recv() {
+                        self.handle(command);
+                    }
+                    true
+                }
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => return,
+            };
+            self.engine.update();
+            if handled || self.engine.needs_update() {
+                self.engine.render();
+            }
+            // only frames that changed are handed over, and an idle engine ticks rarely
+            self.publish();
+        }
+    }
+
+    fn handle(&mut self, command: Command) {
+        let Some(id) = command.view_id() else {
+            match command {
+                Command::SetAssets(assets) => self.engine.set_assets(assets),
+                Command::SetScheme(scheme) => self.engine.set_scheme(&scheme),
+                Command::InterceptNavigation(intercept) => {
+                    self.engine.intercept_navigation(intercept)
+                }
+                Command::ClearData(session, data) => self.engine.clear_data(session, data),
+                _ => (),
+            }
+            return;
+        };
+        // a closed view has nowhere to report errors to
+        let is_removal = matches!(command, Command::RemoveView(_));
+        match self.apply(id, command) {
+            Err(error) if !is_removal => {
+                if let Ok(mut snapshot) = self.snapshot.lock() {
+                    snapshot.errors.push((id, error));
+                    self.notify(&mut snapshot);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn apply(&mut self, id: ViewId, command: Command) -> Result<(), Error> {
+        if let Command::NewView(_, size, content, session) = command {
+            let engine_id = self.engine.new_view(size, content, session)?;
+            self.ids.insert(id, engine_id);
+            return Ok(());
+        }
+
+        let engine = &mut self.engine;
+        let engine_id = self.ids.get(&id).copied().ok_or(Error::UnknownView(id))?;
+        match command {
+            Command::NewView(..)
+            | Command::SetAssets(_)
+            | Command::SetScheme(_)
+            | Command::InterceptNavigation(_)
+            | Command::ClearData(..) => Ok(()),
+            Command::RemoveView(_) => {
+                engine.remove_view(engine_id)?;
+                self.ids.remove(&id);
+                self.last_frames.remove(&id);
+                if let Ok(mut snapshot) = self.snapshot.lock() {
+                    snapshot.views.remove(&id);
+                }
+                Ok(())
+            }
+            Command::Render(_) => engine.request_render(engine_id),
+            Command::Resize(_, size) => engine.resize(engine_id, size),
+            Command::SetScaleFactor(_, scale_factor) => {
+                engine.set_scale_factor(engine_id, scale_factor)
+            }
+            Command::SetZoom(_, zoom) => engine.set_zoom(engine_id, zoom),
+            Command::CaptureFullPage(_) => engine.capture_full_page(engine_id),
+            Command::Focus(_) => engine.focus(engine_id),
+            Command::Unfocus(_) => engine.unfocus(engine_id),
+            Command::Keyboard(_, event) => engine.handle_keyboard_event(engine_id, event),
+            Command::Mouse(_, point, event) => engine.handle_mouse_event(engine_id, point, event),
+            Command::Scroll(_, delta) => engine.scroll(engine_id, delta),
+            Command::Goto(_, page_type) => engine.goto(engine_id, page_type),
+            Command::Refresh(_) => engine.refresh(engine_id),
+            Command::GoForward(_) => engine.go_forward(engine_id),
+            Command::GoBack(_) => engine.go_back(engine_id),
+            Command::EvaluateScript(_, script) => engine.evaluate_script(engine_id, &script),
+            Command::PostMessage(_, message) => engine.post_message(engine_id, &message),
+            Command::Find(_, query, case_sensitive, forward) => {
+                engine.find(engine_id, &query, case_sensitive, forward)
+            }
+            Command::StopFinding(_) => engine.stop_finding(engine_id),
+            Command::Respond(_, request, response) => engine.respond(engine_id, request, response),
+            Command::AnswerDialog(_, dialog, answer) => {
+                engine.answer_dialog(engine_id, dialog, answer)
+            }
+        }
+    }
+
+    /// Hands anything that changed since the last tick over to the ui thread
+    fn publish(&mut self) {
+        // frames are collected before locking, so the ui thread is never kept waiting on them
+        let mut frames = Vec::new();
+        for (&id, &engine_id) in self.ids.iter() {
+            if let Ok(frame) = self.engine.get_view(engine_id) {
+                if !self
+                    .last_frames
+                    .get(&id)
+                    .is_some_and(|last| last.same_pixels(frame))
+                {
+                    self.last_frames.insert(id, frame.clone());
+                    frames.push((id, frame.clone()));
+                }
+            }
+        }
+        let events = self.engine.take_events();
+        let cleared_data = self.engine.take_cleared_data();
+        let needs_update = self.engine.needs_update();
+
+        let Ok(mut snapshot) = self.snapshot.lock() else {
+            return;
+        };
+        let mut changed =
+            !frames.is_empty() || !cleared_data.is_empty() || snapshot.needs_update != needs_update;
+        snapshot.needs_update = needs_update;
+        snapshot.cleared_data.extend(cleared_data);
+
+        for (engine_id, event) in events {
+            if let Some((&id, _)) = self.ids.iter().find(|(_, &view)| view == engine_id) {
+                snapshot.events.push((id, event));
+                changed = true;
+            }
+        }
+
+        for (id, frame) in frames {
+            snapshot.views.entry(id).or_default().frame = Some(frame);
+        }
+
+        for (&id, &engine_id) in self.ids.iter() {
+            let published = snapshot.views.entry(id).or_default();
+            if let Ok(url) = self.engine.get_url(engine_id) {
+                if published.url != url {
+                    published.url = url;
+                    changed = true;
+                }
+            }
+            if let Ok(title) = self.engine.get_title(engine_id) {
+                if published.title != title {
+                    published.title = title;
+                    changed = true;
+                }
+            }
+            if let Ok(cursor) = self.engine.get_cursor(engine_id) {
+                if published.cursor != cursor {
+                    published.cursor = cursor;
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            self.notify(&mut snapshot);
+        }
+    }
+
+    fn notify(&self, snapshot: &mut Snapshot) {
+        // one wake up is enough until the ui thread has synced
+        if !snapshot.changed {
+            snapshot.changed = true;
+            let _ = self.notify.unbounded_send(());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::Mock;
+    use std::time::Instant;
+
+    /// A threaded mock whose engine already has a view, so the host's ids differ from the engine's
+    fn threaded() -> Threaded<Mock> {
+        Threaded::new(|| {
+            let mut mock = Mock::new();
+            let _ = mock.new_view(Size::new(10, 10), None, Session::Default);
+            mock
+        })
+    }
+
+    /// Syncs with the worker until `done` holds, failing if it never catches up
+    fn wait_until(engine: &mut Threaded<Mock>, mut done: impl FnMut(&mut Threaded<Mock>) -> bool) {
+        let start = Instant::now();
+        while !done(engine) {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "the worker fell behind"
+            );
+            thread::sleep(Duration::from_millis(5));
+            engine.update();
+        }
+    }
+
+    fn url(url: &str) -> Option<PageType> {
+        Some(PageType::Url(url.to_string()))
+    }
+
+    #[test]
+    fn commands_reach_the_engine_and_results_come_back() {
+        let mut engine = threaded();
+        let id = engine
+            .new_view(
+                Size::new(800, 600),
+                url("https://example.com"),
+                Session::Default,
+            )
+            .unwrap();
+        wait_until(&mut engine, |engine| {
+            engine.get_url(id).unwrap() == "https://example.com"
+        });
+
+        engine.resize(id, Size::new(400, 300)).unwrap();
+        engine
+            .goto(id, PageType::Url("https://iced.rs".to_string()))
+            .unwrap();
+        wait_until(&mut engine, |engine| {
+            let frame = Engine::get_view(engine, id).unwrap();
+            engine.get_title(id).unwrap() == "https://iced.rs" && frame.width() == 400
+        });
+    }
+
+    #[test]
+    fn events_go_to_the_view_they_happened_in() {
+        let mut engine = threaded();
+        let first = engine
+            .new_view(Size::new(10, 10), None, Session::Default)
+            .unwrap();
+        let second = engine
+            .new_view(Size::new(10, 10), None, Session::Default)
+            .unwrap();
+
+        engine.evaluate_script(second, "1 + 1").unwrap();
+        let mut events = Vec::new();
+        wait_until(&mut engine, |engine| {
+            events.extend(engine.take_events());
+            !events.is_empty()
+        });
+        assert_eq!(
+            events,
+            [(
+                second,
+                ViewEvent::ScriptEvaluated(Ok("undefined".to_string()))
+            )]
+        );
+        assert!(!events.iter().any(|(id, _)| *id == first));
+    }
+
+    #[test]
+    fn errors_come_back_from_the_worker() {
+        let error = Error::Unsupported("clearing".to_string());
+        let expected = error.clone();
+        let mut engine = Threaded::new(move || {
+            let mut mock = Mock::new();
+            mock.fail_clearing_data(error);
+            mock
+        });
+
+        engine.clear_data(None, BrowsingData::ALL);
+        engine.clear_data(None, BrowsingData::ALL);
+        let mut cleared = Vec::new();
+        wait_until(&mut engine, |engine| {
+            cleared.extend(engine.take_cleared_data());
+            cleared.len() == 2
+        });
+        assert_eq!(cleared, [Err(expected), Ok(())]);
+
+        assert_eq!(
+            engine.goto(7, PageType::Html(String::new())),
+            Err(Error::UnknownView(7))
+        );
+    }
+
+    #[test]
+    fn removed_views_are_forgotten() {
+        let mut engine = threaded();
+        let id = engine
+            .new_view(
+                Size::new(10, 10),
+                url("https://example.com"),
+                Session::Default,
+            )
+            .unwrap();
+        let kept = engine
+            .new_view(Size::new(10, 10), None, Session::Default)
+            .unwrap();
+
+        // the removed view's load events may already be on their way
+        engine.remove_view(id).unwrap();
+        engine.evaluate_script(kept, "1 + 1").unwrap();
+        let mut events = Vec::new();
+        wait_until(&mut engine, |engine| {
+            events.extend(engine.take_events());
+            !events.is_empty()
+        });
+        assert!(events.iter().all(|(view, _)| *view == kept));
+        assert_eq!(engine.get_url(id), Err(Error::UnknownView(id)));
+        assert_eq!(engine.request_render(id), Err(Error::UnknownView(id)));
+    }
+}
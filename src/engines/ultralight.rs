@@ -1,25 +1,35 @@
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use clipboard_rs::{Clipboard, ClipboardContext};
 use iced::keyboard::{self};
 use iced::mouse::{self, ScrollDelta};
-use iced::{Point, Size};
+use iced::{Point, Rectangle, Size};
 use rand::Rng;
 use smol_str::SmolStr;
+use std::fs;
+use std::io;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 use std::{env::var, path::Path};
 use ul_next::{
     config::Config,
     event::{self, KeyEventCreationInfo, MouseEvent, ScrollEvent},
     key_code::VirtualKeyCode,
     platform,
-    renderer::Renderer,
+    renderer::{self, Renderer},
     view,
     window::Cursor,
 };
+use url::Url;
 
-use super::{Engine, PageType, PixelFormat, ViewId};
-use crate::ImageInfo;
+use super::{
+    BrowsingData, ConsoleLevel, ConsoleMessage, Dialog, DialogAnswer, DialogId, DialogKind, Engine,
+    FindResult, LoadFailure, NavigationKind, Opener, PageType, PixelFormat, Request, RequestId,
+    Response, Session, ViewEvent, ViewId, MAX_CAPTURE_HEIGHT,
+};
+use crate::{Assets, Error, ImageInfo};
 
 struct UlClipboard {
     ctx: ClipboardContext,
@@ -33,12 +43,207 @@ impl platform::Clipboard for UlClipboard {
     }
 
     fn write_plain_text(&mut self, text: &str) {
-        self.ctx
-            .set_text(text.into())
-            .expect("Failed to set contents of clipboard");
+        // Ultralight gives us no way to report this, so a failed copy is simply dropped
+        let _ = self.ctx.set_text(text.into());
+    }
+}
+
+/// Serves [`Assets`] under `file:///app/`, and every other file from disk
+/// like Ultralight's own platform filesystem
+struct UlFileSystem {
+    base_dir: PathBuf,
+    assets: Arc<RwLock<Assets>>,
+}
+
+impl UlFileSystem {
+    /// Ultralight passes file urls without their `file:///`
+    fn asset_path(path: &str) -> Option<String> {
+        Assets::path(&format!("file:///{}", path.trim_start_matches('/'))).map(str::to_string)
+    }
+
+    fn open_asset(&self, path: &str) -> Option<Vec<u8>> {
+        let assets = self.assets.read().ok()?;
+        assets.get(path).map(<[u8]>::to_vec)
+    }
+}
+
+impl platform::FileSystem for UlFileSystem {
+    fn file_exists(&mut self, path: &str) -> bool {
+        match Self::asset_path(path) {
+            Some(path) => self
+                .assets
+                .read()
+                .is_ok_and(|assets| assets.get(&path).is_some()),
+            None => self.base_dir.join(path).exists(),
+        }
+    }
+
+    fn get_file_mime_type(&mut self, path: &str) -> String {
+        Assets::mime_type(path).to_string()
+    }
+
+    fn get_file_charset(&mut self, _path: &str) -> String {
+        "utf-8".to_string()
+    }
+
+    fn open_file(&mut self, path: &str) -> Option<Vec<u8>> {
+        match Self::asset_path(path) {
+            Some(path) => self.open_asset(&path),
+            None => fs::read(self.base_dir.join(path)).ok(),
+        }
+    }
+}
+
+/// A navigation the app started itself, which goes through without asking
+#[derive(Debug, PartialEq)]
+enum Approval {
+    /// Going to a url, or reloading the page at it
+    Url(String),
+    /// Going through the history, or to html without a url of its own
+    Any,
+}
+
+/// Holds back the navigations a view's page starts itself while the app wants to approve them
+struct NavigationGate {
+    // shared by every view
+    intercept: Arc<AtomicBool>,
+    state: Mutex<GateState>,
+}
+
+impl NavigationGate {
+    fn new(intercept: Arc<AtomicBool>) -> Self {
+        Self {
+            intercept,
+            state: Mutex::new(GateState::default()),
+        }
+    }
+
+    fn with_state<T: Default>(&self, f: impl FnOnce(&mut GateState) -> T) -> T {
+        self.state
+            .lock()
+            .map(|mut state| f(&mut state))
+            .unwrap_or_default()
+    }
+
+    /// Lets the navigation the app is about to start through
+    fn approve(&self, approval: Approval) {
+        self.with_state(|state| state.approved = Some(approval));
+    }
+
+    fn click(&self, link: &str) {
+        self.with_state(|state| state.clicked_link = Some(link.to_string()));
+    }
+
+    /// Whether a main frame load that began should be held back, and why it started
+    fn hold(&self, url: &str) -> Option<NavigationKind> {
+        let intercept = self.intercept.load(Ordering::Relaxed);
+        self.with_state(|state| state.begin(url, intercept))
+    }
+
+    fn finish(&self, url: &str) {
+        self.with_state(|state| state.finish(url));
+    }
+
+    /// Whether the failure of a main frame load should be reported
+    fn fail(&self, url: &str) -> bool {
+        self.with_state(|state| state.fail(url))
+    }
+
+    fn history_changed(&self) {
+        self.with_state(GateState::history_changed);
+    }
+}
+
+/// What a [`NavigationGate`] knows about a view's main frame, kept apart from the view so it can be tested
+#[derive(Debug, Default)]
+struct GateState {
+    // set when the app starts a navigation, until that navigation begins loading
+    approved: Option<Approval>,
+    // the held navigation being stopped, so its cancellation is not reported
+    holding: Option<String>,
+    // the last link the user clicked, to tell link clicks apart from redirects
+    clicked_link: Option<String>,
+}
+
+impl GateState {
+    fn begin(&mut self, url: &str, intercept: bool) -> Option<NavigationKind> {
+        let clicked_link = self.clicked_link.take();
+        // a held navigation that was stopped without failing is over once the next one begins
+        self.holding = None;
+        if self.take_approval(url) || !intercept {
+            return None;
+        }
+        self.holding = Some(url.to_string());
+        Some(match clicked_link {
+            Some(link) if link == url => NavigationKind::LinkClick,
+            _ => NavigationKind::Redirect,
+        })
+    }
+
+    fn finish(&mut self, url: &str) {
+        self.holding = None;
+        self.settle(url);
+    }
+
+    fn fail(&mut self, url: &str) -> bool {
+        self.settle(url);
+        // stopping a held navigation is not a failure
+        self.holding
+            .take_if(|holding| same_url(holding, url))
+            .is_none()
+    }
+
+    /// History navigations served without beginning a new load use up their approval here
+    fn history_changed(&mut self) {
+        self.approved.take_if(|approved| *approved == Approval::Any);
+    }
+
+    /// Forgets the approval of a navigation that ended before it began, like one that failed straight away,
+    /// while loads it replaced ending leave it for the navigation to come
+    fn settle(&mut self, url: &str) {
+        self.approved.take_if(
+            |approved| matches!(approved, Approval::Url(approved) if same_url(approved, url)),
+        );
+    }
+
+    /// Whether the navigation to a url is the one the app approved, using up the approval if so
+    fn take_approval(&mut self, url: &str) -> bool {
+        match self.approved.take() {
+            Some(Approval::Url(approved)) if !same_url(&approved, url) => {
+                // still waiting for the app's own navigation to begin
+                self.approved = Some(Approval::Url(approved));
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+}
+
+/// Whether two urls point to the same place, once Ultralight has normalized one of them
+fn same_url(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
     }
 }
 
+/// The approval for the app going to a page
+fn approval(page_type: &PageType) -> Approval {
+    match page_type {
+        PageType::Url(url) => Approval::Url(url.clone()),
+        PageType::Html(_) => Approval::Any,
+    }
+}
+
+/// How [`Ultralight::clear_data`] clears a session
+enum Clearing {
+    /// Through the pages of the views using it
+    Pages,
+    /// By forgetting it and deleting where it was saved, if anywhere
+    Forget(Option<PathBuf>),
+}
+
 /// Holds Ultralight View info like surfaces for rendering and urls & titles
 pub struct View {
     id: ViewId,
@@ -47,21 +252,72 @@ pub struct View {
     last_frame: ImageInfo,
     was_loading: bool,
     cursor_pos: Point,
+    navigation: Arc<NavigationGate>,
+    session: Session,
+    // zooming is done by scaling the device further, so both are kept to multiply them
+    scale_factor: f32,
+    zoom: f32,
 }
 
 impl View {
+    fn size(&self) -> Size<u32> {
+        Size::new(self.view.width(), self.view.height())
+    }
+
+    /// Draws the page at the view's scale factor, times its zoom
+    fn apply_device_scale(&self) {
+        let device_scale = (self.scale_factor * self.zoom) as f64;
+        if self.view.device_scale() != device_scale {
+            self.view.set_device_scale(device_scale);
+            self.view.set_needs_paint(true);
+        }
+    }
+
+    /// Copies what the view last painted, leaving out the padding Ultralight may add to each row
+    fn copy_frame(&mut self) {
+        let Some(mut surface) = self.view.surface() else {
+            return;
+        };
+        let (width, height) = (surface.width(), surface.height());
+        let row_bytes = surface.row_bytes() as usize;
+        let row = width as usize * 4;
+        let pixels = surface.lock_pixels().map(|pixels| {
+            if row_bytes == row {
+                pixels.to_vec()
+            } else {
+                pixels
+                    .chunks(row_bytes)
+                    .take(height as usize)
+                    .flat_map(|padded| &padded[..row])
+                    .copied()
+                    .collect()
+            }
+        });
+        if let Some(pixels) = pixels {
+            self.last_frame = ImageInfo::new(pixels, PixelFormat::Bgra, width, height);
+            self.was_loading = false;
+        }
+    }
+
     fn update_cursor_pos(&mut self) {
-        let cursor_pos = self.cursor_pos;
-        self.view.fire_mouse_event(
-            MouseEvent::new(
-                ul_next::event::MouseEventType::MouseMoved,
-                cursor_pos.x as i32,
-                cursor_pos.y as i32,
-                ul_next::event::MouseButton::None,
-            )
-            .expect("Ultralight failed to fire mouse input"),
+        self.fire_mouse_event(
+            event::MouseEventType::MouseMoved,
+            self.cursor_pos,
+            event::MouseButton::None,
         );
     }
+
+    /// Events Ultralight fails to create are skipped, like key events
+    fn fire_mouse_event(
+        &self,
+        kind: event::MouseEventType,
+        point: Point,
+        button: event::MouseButton,
+    ) {
+        if let Ok(event) = MouseEvent::new(kind, point.x as i32, point.y as i32, button) {
+            self.view.fire_mouse_event(event);
+        }
+    }
 }
 
 /// Implementation of the Ultralight browsing engine for iced_webivew
@@ -69,61 +325,440 @@ pub struct Ultralight {
     renderer: Renderer,
     view_config: view::ViewConfig,
     views: Vec<View>,
+    // created the first time a view uses them, and kept until they are cleared
+    sessions: Vec<(Session, renderer::Session)>,
+    // where persistent sessions are saved, to clear ones no view opened
+    cache_path: Option<PathBuf>,
+    // outcomes of clear_data waiting to be taken
+    cleared_data: Vec<Result<(), Error>>,
+    // filled in by the views' listeners while the renderer updates
+    events: Arc<Mutex<Vec<(ViewId, ViewEvent)>>>,
+    // shared with the filesystem, which Ultralight calls while the renderer updates
+    assets: Arc<RwLock<Assets>>,
+    // read by the views' bridges whenever a page loads
+    scheme: Arc<Mutex<Option<String>>>,
+    intercept_navigation: Arc<AtomicBool>,
 }
 
 impl Default for Ultralight {
     fn default() -> Self {
-        let config = Config::start().build().expect("Failed to start Ultralight");
-        platform::enable_platform_fontloader();
-        platform::enable_platform_filesystem(platform_filesystem())
-            .expect("Failed to get platform filesystem");
-        platform::set_clipboard(UlClipboard {
-            ctx: ClipboardContext::new().expect("Failed to get ownership of clipboard"),
-        });
-
-        let renderer = Renderer::create(config).expect("Failed to create ultralight renderer");
-        let view_config = view::ViewConfig::start()
-            .initial_device_scale(1.0)
-            .font_family_standard("Arial")
-            .is_accelerated(false)
+        UltralightBuilder::default()
             .build()
-            .unwrap();
-
-        Self {
-            renderer,
-            view_config,
-            views: Vec::new(),
-        }
+            .expect("Failed to start Ultralight")
     }
 }
 
 impl Ultralight {
     /// Creates a new Ultralight adapter
     pub fn new(font: &str, scale: f64) -> Self {
-        Self {
-            view_config: view::ViewConfig::start()
-                .initial_device_scale(scale)
-                .font_family_standard(font)
-                // iced_webview does not currently support acceleration
-                .is_accelerated(false)
-                .build()
-                .unwrap(),
-            ..Default::default()
+        Self::try_new(font, scale).expect("Failed to start Ultralight")
+    }
+
+    /// Creates a new Ultralight adapter, returning an error instead of panicking if it fails to start
+    pub fn try_new(font: &str, scale: f64) -> Result<Self, Error> {
+        Self::builder()
+            .font_family_standard(font)
+            .device_scale(scale)
+            .build()
+    }
+
+    /// Configures an Ultralight adapter before starting it, see [`UltralightBuilder`]
+    pub fn builder() -> UltralightBuilder {
+        UltralightBuilder::default()
+    }
+
+    /// Creates Ultralight's session for a [`Session`] the first time a view uses it
+    fn open_session(&mut self, session: &Session) -> Result<(), Error> {
+        let (persistent, name) = match session {
+            Session::Default => return Ok(()),
+            Session::Persistent(name) => (true, name),
+            Session::Private(name) => (false, name),
+        };
+        if !self.sessions.iter().any(|(known, _)| known == session) {
+            let created = self
+                .renderer
+                .create_session(persistent, name)
+                .map_err(|err| Error::EngineInit(err.to_string()))?;
+            self.sessions.push((session.clone(), created));
+        }
+        Ok(())
+    }
+
+    /// Removes data from sessions, checking every session can be cleared before clearing any of them
+    fn clear_sessions(
+        &mut self,
+        session: Option<Session>,
+        data: BrowsingData,
+    ) -> Result<(), Error> {
+        if data == BrowsingData::default() {
+            return Ok(());
+        }
+        let sessions = match session {
+            Some(session) => vec![session],
+            // the default session only holds something the views using it can reach
+            None => {
+                let mut sessions = Vec::new();
+                let used = self.views.iter().map(|view| &view.session);
+                for session in used.chain(self.sessions.iter().map(|(session, _)| session)) {
+                    if !sessions.contains(session) {
+                        sessions.push(session.clone());
+                    }
+                }
+                sessions
+            }
+        };
+        let clearings = sessions
+            .into_iter()
+            .map(|session| Ok((self.clearing(&session, data)?, session)))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        if data.cache {
+            self.renderer.purge_memory();
+        }
+        // clear what can be cleared before reporting what could not
+        let mut result = Ok(());
+        for (clearing, session) in clearings {
+            result = result.and(match clearing {
+                Clearing::Pages => self.clear_pages(&session, data),
+                Clearing::Forget(disk_path) => self.forget_session(&session, disk_path),
+            });
+        }
+        result
+    }
+
+    /// Works out how a session can be cleared, without changing anything yet
+    fn clearing(&self, session: &Session, data: BrowsingData) -> Result<Clearing, Error> {
+        if self.views.iter().any(|view| view.session == *session) {
+            return Ok(Clearing::Pages);
+        }
+        let opened = self
+            .sessions
+            .iter()
+            .find(|(known, _)| known == session)
+            .map(|(_, ul_session)| ul_session);
+        let disk_path = match (session, opened) {
+            (Session::Default, _) => {
+                return Err(Error::Unsupported(
+                    "Ultralight can only clear its default session through the views using it"
+                        .to_string(),
+                ))
+            }
+            // no view used it since the engine started, so it holds nothing
+            (Session::Private(_), None) => return Ok(Clearing::Forget(None)),
+            (Session::Private(_), Some(_)) => None,
+            (Session::Persistent(_), Some(ul_session)) => {
+                Some(PathBuf::from(ul_session.disk_path()))
+            }
+            (Session::Persistent(name), None) => match &self.cache_path {
+                Some(cache_path) => Some(cache_path.join(name)),
+                None => {
+                    return Err(Error::Unsupported(format!(
+                        "Ultralight can not find {session:?} on disk without a cache path"
+                    )))
+                }
+            },
+        };
+        if data != BrowsingData::ALL {
+            return Err(Error::Unsupported(format!(
+                "Ultralight can only clear every kind of data at once from {session:?}, as no view uses it"
+            )));
+        }
+        Ok(Clearing::Forget(disk_path))
+    }
+
+    /// Clears cookies and storage through the pages of a session's views,
+    /// which reaches the sites they show and the cookies their scripts can see
+    fn clear_pages(&self, session: &Session, data: BrowsingData) -> Result<(), Error> {
+        let mut script = String::new();
+        if data.local_storage {
+            script.push_str(CLEAR_STORAGE_SCRIPT);
+        }
+        if data.cookies {
+            script.push_str(CLEAR_COOKIES_SCRIPT);
+        }
+        if script.is_empty() {
+            return Ok(());
+        }
+        let mut result = Ok(());
+        for view in self.views.iter().filter(|view| view.session == *session) {
+            let outcome = view
+                .view
+                .evaluate_script(&script)
+                .map_err(|err| err.to_string())
+                .and_then(|outcome| outcome.map(drop));
+            if let Err(err) = outcome {
+                let reason = format!("could not run in view {}: {err}", view.id);
+                result = result.and(Err(Error::ClearDataFailed(reason)));
+            }
+        }
+        result
+    }
+
+    /// Clears a session none of the views use by forgetting it and deleting what it saved to disk,
+    /// so the next view to use it starts from scratch
+    fn forget_session(
+        &mut self,
+        session: &Session,
+        disk_path: Option<PathBuf>,
+    ) -> Result<(), Error> {
+        self.sessions.retain(|(known, _)| known != session);
+        match disk_path.map(fs::remove_dir_all) {
+            Some(Err(err)) if err.kind() != io::ErrorKind::NotFound => {
+                Err(Error::ClearDataFailed(err.to_string()))
+            }
+            _ => Ok(()),
         }
     }
 
-    fn get_view(&self, id: ViewId) -> &View {
+    fn get_view(&self, id: ViewId) -> Result<&View, Error> {
         self.views
             .iter()
             .find(|&view| view.id == id)
-            .expect("The requested View id was not found")
+            .ok_or(Error::UnknownView(id))
     }
 
-    fn get_view_mut(&mut self, id: ViewId) -> &mut View {
+    fn get_view_mut(&mut self, id: ViewId) -> Result<&mut View, Error> {
         self.views
             .iter_mut()
             .find(|view| view.id == id)
-            .expect("The requested View id was not found")
+            .ok_or(Error::UnknownView(id))
+    }
+}
+
+/// Configures an [`Ultralight`] engine, starting its renderer once when built
+///
+/// Settings that are not given keep Ultralight's own defaults,
+/// except for the standard font, which defaults to Arial
+///
+/// ```rust,no_run
+/// use iced_webview::Ultralight;
+///
+/// let engine = Ultralight::builder()
+///     .cache_path("./cache")
+///     .user_agent("my-app/1.0")
+///     .font_family_standard("Inter")
+///     .transparent_background(true)
+///     .build()
+///     .expect("Failed to start Ultralight");
+/// ```
+#[derive(Clone, Debug)]
+pub struct UltralightBuilder {
+    cache_path: Option<PathBuf>,
+    resources_path: Option<PathBuf>,
+    user_agent: Option<String>,
+    user_stylesheet: Option<String>,
+    font_family_standard: String,
+    font_family_serif: Option<String>,
+    font_family_fixed: Option<String>,
+    font_family_sans_serif: Option<String>,
+    device_scale: f64,
+    transparent_background: bool,
+    javascript: bool,
+    images: bool,
+    animation_timer_delay: Option<Duration>,
+    memory_cache_size: Option<u32>,
+    font_gamma: Option<f64>,
+}
+
+impl Default for UltralightBuilder {
+    fn default() -> Self {
+        Self {
+            cache_path: None,
+            resources_path: None,
+            user_agent: None,
+            user_stylesheet: None,
+            font_family_standard: "Arial".to_string(),
+            font_family_serif: None,
+            font_family_fixed: None,
+            font_family_sans_serif: None,
+            device_scale: 1.0,
+            transparent_background: false,
+            javascript: true,
+            images: true,
+            animation_timer_delay: None,
+            memory_cache_size: None,
+            font_gamma: None,
+        }
+    }
+}
+
+impl UltralightBuilder {
+    /// The directory [`Session::Persistent`] sessions save their cookies, storage and cache in
+    pub fn cache_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cache_path = Some(path.into());
+        self
+    }
+
+    /// The directory holding Ultralight's runtime resources, like `cacert.pem` and `icudt67l.dat`
+    ///
+    /// Without it, `ULTRALIGHT_RESOURCES_DIR` or the directory linked by the `ultralight-resources` feature is used
+    pub fn resources_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.resources_path = Some(path.into());
+        self
+    }
+
+    /// The user agent every view sends to websites
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// CSS applied to every page before its own styles, like for scrollbars and form fields
+    pub fn user_stylesheet(mut self, css: &str) -> Self {
+        self.user_stylesheet = Some(css.to_string());
+        self
+    }
+
+    /// The font pages use when they do not ask for one
+    pub fn font_family_standard(mut self, font: &str) -> Self {
+        self.font_family_standard = font.to_string();
+        self
+    }
+
+    /// The font used for the generic `serif` family
+    pub fn font_family_serif(mut self, font: &str) -> Self {
+        self.font_family_serif = Some(font.to_string());
+        self
+    }
+
+    /// The font used for the generic `monospace` family
+    pub fn font_family_fixed(mut self, font: &str) -> Self {
+        self.font_family_fixed = Some(font.to_string());
+        self
+    }
+
+    /// The font used for the generic `sans-serif` family
+    pub fn font_family_sans_serif(mut self, font: &str) -> Self {
+        self.font_family_sans_serif = Some(font.to_string());
+        self
+    }
+
+    /// How many pixels views draw per css pixel, 1.0 by default
+    pub fn device_scale(mut self, scale: f64) -> Self {
+        self.device_scale = scale;
+        self
+    }
+
+    /// Lets whatever is behind a view show through where its page has no background
+    pub fn transparent_background(mut self, transparent: bool) -> Self {
+        self.transparent_background = transparent;
+        self
+    }
+
+    /// Whether pages may run JavaScript, enabled by default
+    ///
+    /// Without it, pages have no `window.iced`, so messages, dialogs and the app's scheme stop working
+    pub fn javascript(mut self, enabled: bool) -> Self {
+        self.javascript = enabled;
+        self
+    }
+
+    /// Whether pages load and show images, enabled by default
+    pub fn images(mut self, enabled: bool) -> Self {
+        self.images = enabled;
+        self
+    }
+
+    /// How long to wait between repaints while a css animation runs, a 60th of a second by default
+    pub fn animation_timer_delay(mut self, delay: Duration) -> Self {
+        self.animation_timer_delay = Some(delay);
+        self
+    }
+
+    /// How many bytes of responses are kept in memory to load faster, 64 MiB by default
+    pub fn memory_cache_size(mut self, bytes: u32) -> Self {
+        self.memory_cache_size = Some(bytes);
+        self
+    }
+
+    /// The gamma text is drawn with, raise it for more contrast
+    pub fn font_gamma(mut self, gamma: f64) -> Self {
+        self.font_gamma = Some(gamma);
+        self
+    }
+
+    /// Starts the renderer with these settings
+    pub fn build(self) -> Result<Ultralight, Error> {
+        let resources = resources_dir(self.resources_path)?;
+        let (Some(base_dir), Some(resources_name)) = (resources.parent(), resources.file_name())
+        else {
+            return Err(Error::EngineInit(
+                "resources path needs to point to the resources directory".to_string(),
+            ));
+        };
+
+        // Ultralight loads its resources through the filesystem, relative to its parent
+        let mut config =
+            Config::start().resource_path_prefix(&format!("{}/", resources_name.to_string_lossy()));
+        if let Some(cache_path) = &self.cache_path {
+            config = config.cache_path(&cache_path.to_string_lossy());
+        }
+        if let Some(css) = &self.user_stylesheet {
+            config = config.user_stylesheet(css);
+        }
+        if let Some(delay) = self.animation_timer_delay {
+            config = config.animation_timer_delay(delay.as_secs_f64());
+        }
+        if let Some(size) = self.memory_cache_size {
+            config = config.memory_cache_size(size);
+        }
+        if let Some(gamma) = self.font_gamma {
+            config = config.font_gamma(gamma);
+        }
+        let config = config
+            .build()
+            .ok_or_else(|| Error::EngineInit("Failed to build Ultralight config".to_string()))?;
+
+        platform::enable_platform_fontloader();
+        let assets = Arc::new(RwLock::new(Assets::new()));
+        platform::set_filesystem(UlFileSystem {
+            base_dir: base_dir.to_owned(),
+            assets: assets.clone(),
+        });
+        platform::set_clipboard(UlClipboard {
+            ctx: ClipboardContext::new()
+                .map_err(|err| Error::ClipboardUnavailable(err.to_string()))?,
+        });
+
+        let renderer =
+            Renderer::create(config).map_err(|err| Error::EngineInit(err.to_string()))?;
+
+        let mut view_config = view::ViewConfig::start()
+            .initial_device_scale(self.device_scale)
+            .font_family_standard(&self.font_family_standard)
+            .is_transparent(self.transparent_background)
+            .enable_javascript(self.javascript)
+            .enable_images(self.images)
+            // iced_webview does not currently support acceleration
+            .is_accelerated(false);
+        if let Some(font) = &self.font_family_serif {
+            view_config = view_config.font_family_serif(font);
+        }
+        if let Some(font) = &self.font_family_fixed {
+            view_config = view_config.font_family_fixed(font);
+        }
+        if let Some(font) = &self.font_family_sans_serif {
+            view_config = view_config.font_family_sans_serif(font);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            view_config = view_config.user_agent(user_agent);
+        }
+        let view_config = view_config.build().ok_or_else(|| {
+            Error::EngineInit("Failed to build Ultralight view config".to_string())
+        })?;
+
+        Ok(Ultralight {
+            renderer,
+            view_config,
+            views: Vec::new(),
+            sessions: Vec::new(),
+            cache_path: self.cache_path,
+            cleared_data: Vec::new(),
+            events: Arc::new(Mutex::new(Vec::new())),
+            assets,
+            scheme: Arc::new(Mutex::new(None)),
+            intercept_navigation: Arc::new(AtomicBool::new(false)),
+        })
     }
 }
 
@@ -132,51 +767,71 @@ impl Engine for Ultralight {
         self.renderer.update();
     }
 
-    fn render(&mut self, size: Size<u32>) {
+    fn render(&mut self) {
+        // note which views changed before painting them clears it
+        let changed: Vec<bool> = self
+            .views
+            .iter_mut()
+            .map(|view| {
+                view.update_cursor_pos();
+                view.view.needs_paint() || view.was_loading && !view.view.is_loading()
+            })
+            .collect();
+        self.renderer.render();
+
         // for each view save frame
-        for view in self.views.iter_mut() {
-            view.update_cursor_pos();
-            if view.view.needs_paint() || view.was_loading && !view.view.is_loading() {
-                if let Some(pixels) = view.view.surface().unwrap().lock_pixels() {
-                    view.last_frame =
-                        ImageInfo::new(pixels.to_vec(), PixelFormat::Bgra, size.width, size.height);
-                    view.was_loading = false;
-                }
+        for (view, changed) in self.views.iter_mut().zip(changed) {
+            if changed {
+                view.copy_frame();
             }
         }
     }
 
-    fn request_render(&mut self, id: ViewId, size: Size<u32>) {
-        self.get_view_mut(id).update_cursor_pos();
-        self.get_view(id).view.set_needs_paint(true);
+    fn take_events(&mut self) -> Vec<(ViewId, ViewEvent)> {
+        self.events
+            .lock()
+            .map(|mut events| std::mem::take(&mut *events))
+            .unwrap_or_default()
+    }
+
+    fn needs_update(&self) -> bool {
+        self.views
+            .iter()
+            .any(|view| view.was_loading || view.view.is_loading() || view.view.needs_paint())
+    }
+
+    fn request_render(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view_mut(id)?.update_cursor_pos();
+        self.get_view(id)?.view.set_needs_paint(true);
         self.renderer.render();
-        if let Some(pixels) = self.get_view(id).view.surface().unwrap().lock_pixels() {
-            self.get_view_mut(id).last_frame =
-                ImageInfo::new(pixels.to_vec(), PixelFormat::Bgra, size.width, size.height);
-            self.get_view_mut(id).was_loading = false
-        }
+        self.get_view_mut(id)?.copy_frame();
+        Ok(())
     }
 
-    fn new_view(&mut self, size: Size<u32>, page_type: Option<PageType>) -> ViewId {
+    fn new_view(
+        &mut self,
+        size: Size<u32>,
+        page_type: Option<PageType>,
+        session: Session,
+    ) -> Result<ViewId, Error> {
         let id = rand::thread_rng().gen();
 
+        self.open_session(&session)?;
+        // the default session is used when none is given
+        let ul_session = self
+            .sessions
+            .iter()
+            .find(|(known, _)| *known == session)
+            .map(|(_, ul_session)| ul_session);
         let view = self
             .renderer
-            .create_view(size.width, size.height, &self.view_config, None)
-            .expect("Failed to create view");
-
-        // TODO: debug why new views are slanted unless do + 10/ - 10
-        // maybe causes the fuzzyness
-        view.resize(size.width + 10, size.height - 10);
-
-        let surface = view.surface().expect("Failed to get surface of new view");
-        // RGBA - ensure it has the right diamentions
-        debug_assert!(surface.row_bytes() / size.width == 4);
+            .create_view(size.width, size.height, &self.view_config, ul_session)
+            .ok_or_else(|| Error::EngineInit("Failed to create view".to_string()))?;
 
         let cursor = Arc::new(RwLock::new(mouse::Interaction::Idle));
         let cb_cursor = cursor.clone();
         view.set_change_cursor_callback(move |_view, cursor_update| {
-            *cb_cursor.write().expect("Failed to write cursor status") = match cursor_update {
+            let cursor = match cursor_update {
                 Cursor::None => mouse::Interaction::Idle,
                 Cursor::Pointer => mouse::Interaction::Idle,
                 Cursor::Hand => mouse::Interaction::Pointer,
@@ -193,8 +848,16 @@ impl Engine for Ultralight {
                 Cursor::ZoomOut => mouse::Interaction::ZoomIn,
                 _ => mouse::Interaction::Pointer,
             };
+            if let Ok(mut cb_cursor) = cb_cursor.write() {
+                *cb_cursor = cursor;
+            }
         });
 
+        let navigation = Arc::new(NavigationGate::new(self.intercept_navigation.clone()));
+        set_load_listeners(&view, id, &self.events, &navigation);
+        set_message_bridge(&view, id, &self.events, &self.scheme, &navigation);
+
+        let scale_factor = view.device_scale() as f32;
         let view = View {
             id,
             view,
@@ -202,63 +865,154 @@ impl Engine for Ultralight {
             last_frame: ImageInfo::blank(size.width, size.height),
             was_loading: true,
             cursor_pos: Point::default(),
+            navigation,
+            session,
+            scale_factor,
+            zoom: 1.0,
         };
         if let Some(page_type) = page_type {
+            view.navigation.approve(approval(&page_type));
             match page_type {
-                PageType::Url(url) => view.view.load_url(&url).expect("Failed to load url"),
-                PageType::Html(html) => view
-                    .view
-                    .load_html(&html)
-                    .expect("Failed to load custom html"),
+                PageType::Url(url) => view.view.load_url(&url),
+                PageType::Html(html) => view.view.load_html(&html),
             }
+            .map_err(|err| Error::LoadFailed(err.to_string()))?;
             view.view.set_needs_paint(true);
         }
         self.views.push(view);
-        id
+        // paint straight away, so the view has a frame before its page loads
+        self.request_render(id)?;
+        Ok(id)
     }
 
-    fn remove_view(&mut self, id: ViewId) {
+    fn remove_view(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view(id)?;
         self.views.retain(|view| view.id != id);
+        Ok(())
+    }
+
+    fn set_assets(&mut self, assets: Assets) {
+        if let Ok(mut current) = self.assets.write() {
+            *current = assets;
+        }
+    }
+
+    /// Only `fetch` is routed to the scheme, and only from the app's own pages, those under
+    /// [`Assets::URL`] or loaded from html, so websites can not reach it.
+    /// Pages already shown pick it up when they next load
+    fn set_scheme(&mut self, scheme: &str) {
+        if let Ok(mut current) = self.scheme.lock() {
+            *current = Some(scheme.to_string());
+        }
+    }
+
+    fn respond(&mut self, id: ViewId, request: RequestId, response: Response) -> Result<(), Error> {
+        let headers = response
+            .headers
+            .iter()
+            .map(|(name, value)| format!("[{}, {}]", js_string(name), js_string(value)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let script = format!(
+            r#"window.iced && window.iced.__respond({request}, {}, [{headers}], "{}")"#,
+            response.status,
+            BASE64_STANDARD.encode(&response.body)
+        );
+        // the page may have navigated away, leaving nobody waiting for the response
+        let _ = self.get_view(id)?.view.evaluate_script(&script);
+        Ok(())
+    }
+
+    fn answer_dialog(
+        &mut self,
+        id: ViewId,
+        dialog: DialogId,
+        answer: DialogAnswer,
+    ) -> Result<(), Error> {
+        let (accepted, text) = match answer {
+            DialogAnswer::Dismiss => (false, "null".to_string()),
+            DialogAnswer::Accept => (true, "null".to_string()),
+            DialogAnswer::Text(text) => (true, js_string(&text)),
+        };
+        let script =
+            format!("window.iced && window.iced.__answerDialog({dialog}, {accepted}, {text})");
+        // the page may have navigated away, leaving nobody waiting for the answer
+        let _ = self.get_view(id)?.view.evaluate_script(&script);
+        Ok(())
+    }
+
+    fn intercept_navigation(&mut self, intercept: bool) {
+        self.intercept_navigation
+            .store(intercept, Ordering::Relaxed);
+    }
+
+    /// Ultralight can not clear a session itself, so cookies and storage are cleared through the pages
+    /// of the session's views, which only reaches their sites and the cookies scripts can see.
+    /// A session none of the views use is forgotten and deleted from disk instead,
+    /// which clears every kind of data at once, and the cache is freed from memory for every session
+    fn clear_data(&mut self, session: Option<Session>, data: BrowsingData) {
+        let outcome = self.clear_sessions(session, data);
+        self.cleared_data.push(outcome);
     }
 
-    fn goto(&mut self, id: ViewId, page_type: PageType) {
-        *self
-            .get_view(id)
-            .cursor
-            .write()
-            .expect("Failed cursor poisoned") = mouse::Interaction::Working;
+    fn take_cleared_data(&mut self) -> Vec<Result<(), Error>> {
+        std::mem::take(&mut self.cleared_data)
+    }
+
+    fn goto(&mut self, id: ViewId, page_type: PageType) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        if let Ok(mut cursor) = view.cursor.write() {
+            *cursor = mouse::Interaction::Working;
+        }
+        view.navigation.approve(approval(&page_type));
         match page_type {
-            PageType::Url(url) => self
-                .get_view_mut(id)
-                .view
-                .load_url(&url)
-                .expect("Failed to load url"),
-            PageType::Html(html) => self
-                .get_view_mut(id)
-                .view
-                .load_html(&html)
-                .expect("Failed to load given html"),
+            PageType::Url(url) => view.view.load_url(&url),
+            PageType::Html(html) => view.view.load_html(&html),
         }
-        self.get_view_mut(id).was_loading = true;
+        .map_err(|err| Error::LoadFailed(err.to_string()))?;
+        view.was_loading = true;
+        Ok(())
     }
 
-    fn focus(&mut self) {
-        self.views.iter().for_each(|view| view.view.focus());
+    fn focus(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view(id)?.view.focus();
+        Ok(())
     }
 
-    fn unfocus(&self) {
-        self.views.iter().for_each(|view| view.view.unfocus());
+    fn unfocus(&mut self, id: ViewId) -> Result<(), Error> {
+        self.get_view(id)?.view.unfocus();
+        Ok(())
     }
 
-    fn resize(&mut self, size: Size<u32>) {
-        self.views.iter().for_each(|view| {
-            view.view.resize(size.width, size.height);
-            view.view.surface().unwrap().resize(size.width, size.height);
-            view.view.set_needs_paint(true);
-        })
+    fn has_focus(&self, id: ViewId) -> Result<bool, Error> {
+        Ok(self.get_view(id)?.view.has_focus())
+    }
+
+    fn resize(&mut self, id: ViewId, size: Size<u32>) -> Result<(), Error> {
+        let view = self.get_view(id)?;
+        view.view.resize(size.width, size.height);
+        if let Some(surface) = view.view.surface() {
+            surface.resize(size.width, size.height);
+        }
+        view.view.set_needs_paint(true);
+        Ok(())
+    }
+
+    fn set_scale_factor(&mut self, id: ViewId, scale_factor: f32) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.scale_factor = scale_factor;
+        view.apply_device_scale();
+        Ok(())
     }
 
-    fn handle_keyboard_event(&mut self, id: ViewId, event: keyboard::Event) {
+    fn set_zoom(&mut self, id: ViewId, zoom: f32) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.zoom = zoom;
+        view.apply_device_scale();
+        Ok(())
+    }
+
+    fn handle_keyboard_event(&mut self, id: ViewId, event: keyboard::Event) -> Result<(), Error> {
         let key_event = match event {
             keyboard::Event::KeyPressed {
                 key,
@@ -292,142 +1046,647 @@ impl Engine for Ultralight {
             }
         };
 
+        let view = self.get_view_mut(id)?;
         if let Some(key_event) = key_event {
-            self.get_view_mut(id).view.fire_key_event(key_event);
+            view.view.fire_key_event(key_event);
         }
+        Ok(())
     }
 
-    fn handle_mouse_event(&mut self, id: ViewId, point: Point, event: mouse::Event) {
-        match event {
-            mouse::Event::ButtonReleased(mouse::Button::Forward) => self.go_forward(id),
-            mouse::Event::ButtonReleased(mouse::Button::Back) => self.go_back(id),
+    fn handle_mouse_event(
+        &mut self,
+        id: ViewId,
+        point: Point,
+        event: mouse::Event,
+    ) -> Result<(), Error> {
+        let (kind, button) = match event {
+            mouse::Event::ButtonReleased(mouse::Button::Forward) => return self.go_forward(id),
+            mouse::Event::ButtonReleased(mouse::Button::Back) => return self.go_back(id),
             mouse::Event::ButtonPressed(mouse::Button::Left) => {
-                self.get_view_mut(id).view.fire_mouse_event(
-                    MouseEvent::new(
-                        ul_next::event::MouseEventType::MouseDown,
-                        point.x as i32,
-                        point.y as i32,
-                        ul_next::event::MouseButton::Left,
-                    )
-                    .expect("Ultralight failed to fire mouse input"),
-                );
+                (event::MouseEventType::MouseDown, event::MouseButton::Left)
             }
             mouse::Event::ButtonReleased(mouse::Button::Left) => {
-                self.get_view_mut(id).view.fire_mouse_event(
-                    MouseEvent::new(
-                        ul_next::event::MouseEventType::MouseUp,
-                        point.x as i32,
-                        point.y as i32,
-                        ul_next::event::MouseButton::Left,
-                    )
-                    .expect("Ultralight failed to fire mouse input"),
-                );
+                (event::MouseEventType::MouseUp, event::MouseButton::Left)
             }
             mouse::Event::ButtonPressed(mouse::Button::Right) => {
-                self.get_view_mut(id).view.fire_mouse_event(
-                    MouseEvent::new(
-                        ul_next::event::MouseEventType::MouseDown,
-                        point.x as i32,
-                        point.y as i32,
-                        ul_next::event::MouseButton::Right,
-                    )
-                    .expect("Ultralight failed to fire mouse input"),
-                );
+                (event::MouseEventType::MouseDown, event::MouseButton::Right)
             }
             mouse::Event::ButtonReleased(mouse::Button::Right) => {
-                self.get_view_mut(id).view.fire_mouse_event(
-                    MouseEvent::new(
-                        ul_next::event::MouseEventType::MouseUp,
-                        point.x as i32,
-                        point.y as i32,
-                        ul_next::event::MouseButton::Right,
-                    )
-                    .expect("Ultralight failed to fire mouse input"),
-                );
+                (event::MouseEventType::MouseUp, event::MouseButton::Right)
             }
             mouse::Event::CursorMoved { position: _ } => {
-                self.get_view_mut(id).cursor_pos = point;
+                self.get_view_mut(id)?.cursor_pos = point;
+                return Ok(());
             }
-            mouse::Event::WheelScrolled { delta } => self.scroll(id, delta),
-            mouse::Event::CursorLeft => {
-                self.unfocus();
-            }
-            mouse::Event::CursorEntered => {
-                self.focus();
-            }
-            _ => (),
-        }
+            mouse::Event::WheelScrolled { delta } => return self.scroll(id, delta),
+            _ => return Ok(()),
+        };
+        self.get_view(id)?.fire_mouse_event(kind, point, button);
+        Ok(())
     }
 
-    fn refresh(&mut self, id: ViewId) {
-        self.get_view_mut(id).view.reload();
+    fn refresh(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        view.navigation.approve(match view.view.url() {
+            Ok(url) => Approval::Url(url),
+            Err(_) => Approval::Any,
+        });
+        view.view.reload();
+        Ok(())
     }
 
-    fn go_forward(&mut self, id: ViewId) {
-        self.get_view_mut(id).view.go_forward();
+    fn go_forward(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        // with nowhere to go, the approval would let the page's next navigation through
+        if view.view.can_go_forward() {
+            view.navigation.approve(Approval::Any);
+            view.view.go_forward();
+        }
+        Ok(())
     }
 
-    fn go_back(&mut self, id: ViewId) {
-        self.get_view_mut(id).view.go_back();
+    fn go_back(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view_mut(id)?;
+        if view.view.can_go_back() {
+            view.navigation.approve(Approval::Any);
+            view.view.go_back();
+        }
+        Ok(())
     }
 
-    fn scroll(&mut self, id: ViewId, delta: mouse::ScrollDelta) {
-        let scroll_event = match delta {
-            ScrollDelta::Lines { x, y } => ScrollEvent::new(
-                ul_next::event::ScrollEventType::ScrollByPixel,
-                x as i32 * 100,
-                y as i32 * 100,
-            )
-            .unwrap(),
-            ScrollDelta::Pixels { x, y } => ScrollEvent::new(
-                ul_next::event::ScrollEventType::ScrollByPixel,
-                x as i32,
-                y as i32,
-            )
-            .unwrap(),
+    fn scroll(&mut self, id: ViewId, delta: mouse::ScrollDelta) -> Result<(), Error> {
+        let (x, y) = match delta {
+            ScrollDelta::Lines { x, y } => (x as i32 * 100, y as i32 * 100),
+            ScrollDelta::Pixels { x, y } => (x as i32, y as i32),
         };
-        self.get_view_mut(id).view.fire_scroll_event(scroll_event);
+        let view = self.get_view(id)?;
+        // like other input, a scroll Ultralight fails to create is skipped
+        if let Ok(event) = ScrollEvent::new(event::ScrollEventType::ScrollByPixel, x, y) {
+            view.view.fire_scroll_event(event);
+        }
+        Ok(())
     }
 
-    fn get_url(&self, id: ViewId) -> String {
-        self.get_view(id).view.url().unwrap_or_default()
+    fn evaluate_script(&mut self, id: ViewId, script: &str) -> Result<(), Error> {
+        // failing to pass the script to Ultralight is reported like an exception,
+        // so every script still gets exactly one outcome
+        let outcome = self
+            .get_view(id)?
+            .view
+            .evaluate_script(script)
+            .unwrap_or_else(|err| Err(err.to_string()));
+        if let Ok(mut events) = self.events.lock() {
+            events.push((id, ViewEvent::ScriptEvaluated(outcome)));
+        }
+        Ok(())
     }
 
-    fn get_title(&self, id: ViewId) -> String {
-        self.get_view(id).view.title().unwrap_or_default()
+    fn post_message(&mut self, id: ViewId, message: &str) -> Result<(), Error> {
+        let script = format!(
+            r#"window.iced && window.iced.dispatchEvent(new MessageEvent("message", {{ data: {} }}))"#,
+            js_string(message)
+        );
+        // a page without scripts has no window.iced, so there is nobody to tell
+        let _ = self.get_view(id)?.view.evaluate_script(&script);
+        Ok(())
     }
 
-    fn get_cursor(&self, id: ViewId) -> mouse::Interaction {
-        match self.get_view(id).cursor.read() {
+    fn find(
+        &mut self,
+        id: ViewId,
+        query: &str,
+        case_sensitive: bool,
+        forward: bool,
+    ) -> Result<(), Error> {
+        let script = format!(
+            "{FIND_SCRIPT}({}, {case_sensitive}, {forward})",
+            js_string(query)
+        );
+        // a page that can not run scripts, like while loading, has nothing to find yet
+        let (active_match, total_matches) = self
+            .get_view(id)?
+            .view
+            .evaluate_script(&script)
+            .ok()
+            .and_then(Result::ok)
+            .and_then(|result| {
+                let (active, total) = result.split_once(',')?;
+                Some((active.parse().ok()?, total.parse().ok()?))
+            })
+            .unwrap_or((0, 0));
+        if let Ok(mut events) = self.events.lock() {
+            let result = FindResult {
+                query: query.to_string(),
+                case_sensitive,
+                active_match,
+                total_matches,
+            };
+            events.push((id, ViewEvent::FindResult(result)));
+        }
+        Ok(())
+    }
+
+    fn stop_finding(&mut self, id: ViewId) -> Result<(), Error> {
+        // a page that can not run scripts has nothing selected either
+        let _ = self.get_view(id)?.view.evaluate_script(STOP_FINDING_SCRIPT);
+        Ok(())
+    }
+
+    fn get_url(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self.get_view(id)?.view.url().unwrap_or_default())
+    }
+
+    fn get_title(&self, id: ViewId) -> Result<String, Error> {
+        Ok(self.get_view(id)?.view.title().unwrap_or_default())
+    }
+
+    fn get_session(&self, id: ViewId) -> Result<Session, Error> {
+        Ok(self.get_view(id)?.session.clone())
+    }
+
+    fn get_cursor(&self, id: ViewId) -> Result<mouse::Interaction, Error> {
+        Ok(match self.get_view(id)?.cursor.read() {
             Ok(cursor) => *cursor,
             Err(_) => mouse::Interaction::Working,
+        })
+    }
+
+    fn get_zoom(&self, id: ViewId) -> Result<f32, Error> {
+        Ok(self.get_view(id)?.zoom)
+    }
+
+    fn get_view(&self, id: ViewId) -> Result<&ImageInfo, Error> {
+        Ok(&self.get_view(id)?.last_frame)
+    }
+
+    /// Ultralight only draws what fits in a view, so the view is stretched to the height of its page
+    /// while it renders, then shrunk back and scrolled to where it was
+    ///
+    /// The view keeps showing its last frame meanwhile, until it is painted again at its own size
+    fn capture_full_page(&mut self, id: ViewId) -> Result<(), Error> {
+        let view = self.get_view(id)?;
+        let size = view.size();
+        let shown = view.last_frame.clone();
+        let device_scale = view.view.device_scale();
+        let measured: Vec<f64> = view
+            .view
+            .evaluate_script(MEASURE_PAGE_SCRIPT)
+            .ok()
+            .and_then(Result::ok)
+            .map(|measured| measured.split(',').filter_map(|n| n.parse().ok()).collect())
+            .unwrap_or_default();
+        // a page that can not run scripts yet is captured at the height of the view
+        let (scroll, height) = match measured[..] {
+            [x, y, height] => (Some((x, y)), (height * device_scale).ceil() as u32),
+            _ => (None, size.height),
+        };
+        let height = height.max(size.height).min(MAX_CAPTURE_HEIGHT);
+
+        self.resize(id, Size::new(size.width, height))?;
+        self.renderer.update();
+        self.request_render(id)?;
+        let image = self.get_view(id)?.last_frame.clone();
+
+        // resizing leaves the view needing paint, so the next render shows it once it has scrolled back,
+        // rather than the stretched frame or one painted before the page caught up
+        self.resize(id, size)?;
+        if let Some((x, y)) = scroll {
+            let script = format!("window.scrollTo({x}, {y})");
+            let _ = self.get_view(id)?.view.evaluate_script(&script);
+        }
+        self.renderer.update();
+        self.get_view_mut(id)?.last_frame = shown;
+
+        if let Ok(mut events) = self.events.lock() {
+            events.push((id, ViewEvent::FullPageCaptured(image)));
+        }
+        Ok(())
+    }
+}
+
+/// Reports the main frame's load lifecycle, sub frames like iframes are ignored
+///
+/// Navigations the gate holds back are stopped as soon as they begin
+fn set_load_listeners(
+    view: &view::View,
+    id: ViewId,
+    events: &Arc<Mutex<Vec<(ViewId, ViewEvent)>>>,
+    navigation: &Arc<NavigationGate>,
+) {
+    let push = move |events: &Arc<Mutex<Vec<(ViewId, ViewEvent)>>>, event| {
+        if let Ok(mut events) = events.lock() {
+            events.push((id, event));
+        }
+    };
+
+    let cb_events = events.clone();
+    let cb_navigation = navigation.clone();
+    view.set_begin_loading_callback(move |view, _frame_id, is_main_frame, url| {
+        if is_main_frame {
+            match cb_navigation.hold(&url) {
+                Some(kind) => {
+                    view.stop();
+                    push(&cb_events, ViewEvent::NavigationRequested(url, kind));
+                }
+                None => push(&cb_events, ViewEvent::LoadStarted(url)),
+            }
         }
+    });
+    let cb_events = events.clone();
+    view.set_dom_ready_callback(move |_view, _frame_id, is_main_frame, url| {
+        if is_main_frame {
+            push(&cb_events, ViewEvent::DomReady(url));
+        }
+    });
+    let cb_events = events.clone();
+    let cb_navigation = navigation.clone();
+    view.set_finish_loading_callback(move |_view, _frame_id, is_main_frame, url| {
+        if is_main_frame {
+            cb_navigation.finish(&url);
+            push(&cb_events, ViewEvent::LoadFinished(url));
+        }
+    });
+    let cb_events = events.clone();
+    let cb_navigation = navigation.clone();
+    view.set_fail_loading_callback(
+        move |_view, _frame_id, is_main_frame, url, description, _error_domain, code| {
+            if is_main_frame && cb_navigation.fail(&url) {
+                push(
+                    &cb_events,
+                    ViewEvent::LoadFailed(LoadFailure {
+                        url,
+                        code,
+                        description,
+                    }),
+                );
+            }
+        },
+    );
+    let cb_events = events.clone();
+    let cb_navigation = navigation.clone();
+    view.set_update_history_callback(move |_view| {
+        cb_navigation.history_changed();
+        push(&cb_events, ViewEvent::HistoryChanged);
+    });
+    // the app decides whether to open a view for the window, so Ultralight never creates one
+    let cb_events = events.clone();
+    view.set_create_child_view_callback(
+        move |_view, opener_url, target_url, is_popup, popup_rect| {
+            let rect = (popup_rect.right > popup_rect.left && popup_rect.bottom > popup_rect.top)
+                .then(|| Rectangle {
+                    x: popup_rect.left,
+                    y: popup_rect.top,
+                    width: popup_rect.right - popup_rect.left,
+                    height: popup_rect.bottom - popup_rect.top,
+                });
+            push(
+                &cb_events,
+                ViewEvent::NewWindowRequested(
+                    target_url,
+                    Opener {
+                        url: opener_url,
+                        is_popup,
+                        rect,
+                    },
+                ),
+            );
+            None
+        },
+    );
+}
+
+// Empties the storage of the page's site
+const CLEAR_STORAGE_SCRIPT: &str =
+    "try { localStorage.clear(); sessionStorage.clear(); } catch (e) {}\n";
+// Expires every cookie of the page's site that scripts can see,
+// pages without a site of their own, like `about:blank`, have none
+const CLEAR_COOKIES_SCRIPT: &str = r#"try {
+    document.cookie.split(";").forEach((cookie) => {
+        const name = cookie.split("=")[0].trim();
+        if (name) document.cookie = name + "=; expires=Thu, 01 Jan 1970 00:00:00 GMT; path=/";
+    });
+} catch (e) {}
+"#;
+
+// Measures where the page is scrolled to and how tall it is, in css pixels
+const MEASURE_PAGE_SCRIPT: &str =
+    "[window.scrollX, window.scrollY, document.documentElement.scrollHeight].join()";
+
+// Selects the next or previous match of a query in the page and returns "active,total",
+// remembering the selected match in `window.__icedFind` so the next find moves on from it
+//
+// Only text within a single node is matched, like browsers do within a single element
+const FIND_SCRIPT: &str = r#"(function (query, caseSensitive, forward) {
+    const fold = (text) => (caseSensitive ? text : text.toLowerCase());
+    const needle = fold(query);
+    const matches = [];
+    const walker = document.createTreeWalker(document.body || document.documentElement, NodeFilter.SHOW_TEXT, {
+        acceptNode: (node) =>
+            /^(SCRIPT|STYLE|NOSCRIPT)$/.test(node.parentNode.nodeName)
+                ? NodeFilter.FILTER_REJECT
+                : NodeFilter.FILTER_ACCEPT,
+    });
+    while (needle && walker.nextNode()) {
+        const text = fold(walker.currentNode.data);
+        for (let at = text.indexOf(needle); at !== -1; at = text.indexOf(needle, at + needle.length)) {
+            matches.push([walker.currentNode, at]);
+        }
+    }
+    const last = window.__icedFind;
+    const again = last && last.query === query && last.caseSensitive === caseSensitive && last.index >= 0;
+    let index = -1;
+    if (matches.length > 0) {
+        index = again ? last.index + (forward ? 1 : -1) : forward ? 0 : matches.length - 1;
+        index = (index + matches.length) % matches.length;
     }
+    window.__icedFind = { query, caseSensitive, index };
+    const selection = window.getSelection();
+    selection.removeAllRanges();
+    if (index >= 0) {
+        const [node, at] = matches[index];
+        const range = document.createRange();
+        range.setStart(node, at);
+        range.setEnd(node, Math.min(at + needle.length, node.data.length));
+        selection.addRange(range);
+        node.parentNode.scrollIntoView({ block: "center" });
+    }
+    return index + 1 + "," + matches.length;
+})"#;
+// Clears the match the last find selected
+const STOP_FINDING_SCRIPT: &str =
+    "if (window.__icedFind) { window.__icedFind = undefined; window.getSelection().removeAllRanges(); }";
+
+/// The prefixes that mark a view's console messages as really coming from its bridge,
+/// random for each view so pages can not forge them
+struct Markers {
+    // messages sent with window.iced.postMessage
+    message: String,
+    // requests to the app's scheme
+    request: String,
+    // dialogs opened with alert, or window.iced's alert, confirm or prompt
+    dialog: String,
+    // the link the user clicked
+    link: String,
+}
 
-    fn get_view(&self, id: ViewId) -> &ImageInfo {
-        &self.get_view(id).last_frame
+impl Markers {
+    fn new() -> Self {
+        let nonce: u64 = rand::thread_rng().gen();
+        let marker = |kind: &str| format!("__iced_webview_{kind}_{nonce:016x}__:");
+        Self {
+            message: marker("message"),
+            request: marker("request"),
+            dialog: marker("dialog"),
+            link: marker("link"),
+        }
     }
 }
 
-fn platform_filesystem() -> PathBuf {
-    let env = var("ULTRALIGHT_RESOURCES_DIR");
-    let resources_path: PathBuf = match env {
-        Ok(env) => PathBuf::from_str(&env)
-            .expect("Failed to get path from ultralight resources enviroment varible"),
-        Err(_) => {
-            // env not set - check if its been symlinked by build.rs
-            match Path::new("./resources").exists() {
-                    true => Path::new("./resources").to_owned(),
-                    false => panic!("ULTRALIGHT_RESOURCES_DIR was not set and ultralight-resources feature was not enabled"),
+/// Gives every page a `window.iced` to talk to the app through, and reports its console
+///
+/// Ultralight has no way to call into Rust from JavaScript,
+/// so messages, requests, dialogs and link clicks travel as marked console messages.
+/// The bridge logs through the `console.log` it found before the page's own scripts ran,
+/// and keeps its markers out of their reach
+fn set_message_bridge(
+    view: &view::View,
+    id: ViewId,
+    events: &Arc<Mutex<Vec<(ViewId, ViewEvent)>>>,
+    scheme: &Arc<Mutex<Option<String>>>,
+    navigation: &Arc<NavigationGate>,
+) {
+    let markers = Arc::new(Markers::new());
+    let cb_markers = markers.clone();
+    let cb_scheme = scheme.clone();
+    view.set_window_object_ready_callback(move |view, _frame_id, is_main_frame, url| {
+        if is_main_frame {
+            let markers = &cb_markers;
+            let mut script = format!(
+                r#"(() => {{
+                    const log = console.log.bind(console);
+                    const MESSAGE = {message};
+                    const DIALOG = {dialog};
+                    const LINK = {link};
+                    const iced = new EventTarget();
+                    iced.postMessage = (message) => log(
+                        MESSAGE + (typeof message === "string" ? message : JSON.stringify(message))
+                    );
+                    window.iced = iced;
+                    const dialogs = new Map();
+                    let nextDialog = 0;
+                    const openDialog = (kind, message, value) => new Promise((resolve) => {{
+                        const id = nextDialog++;
+                        dialogs.set(id, {{ kind, value, resolve }});
+                        log(DIALOG + [id, kind, value.replace(/\n/g, " "), message].join("\n"));
+                    }});
+                    iced.__answerDialog = (id, accepted, text) => {{
+                        const dialog = dialogs.get(id);
+                        dialogs.delete(id);
+                        if (!dialog) return;
+                        if (dialog.kind === "confirm") dialog.resolve(accepted);
+                        else if (dialog.kind === "prompt") dialog.resolve(accepted ? (text === null ? dialog.value : text) : null);
+                        else dialog.resolve(undefined);
+                    }};
+                    iced.alert = (message = "") => openDialog("alert", String(message), "");
+                    iced.confirm = (message = "") => openDialog("confirm", String(message), "");
+                    iced.prompt = (message = "", value = "") => openDialog("prompt", String(message), String(value));
+                    // an alert returns nothing, so it can be shown by the app without being waited on,
+                    // while the blocking confirm and prompt are left to Ultralight
+                    window.alert = (message) => {{ iced.alert(message); }};
+                    document.addEventListener("click", (event) => {{
+                        const link = event.target.closest && event.target.closest("a[href]");
+                        if (link) log(LINK + link.href);
+                    }}, true);
+                }})();"#,
+                message = js_string(&markers.message),
+                dialog = js_string(&markers.dialog),
+                link = js_string(&markers.link),
+            );
+            let scheme = cb_scheme.lock().ok().and_then(|scheme| scheme.clone());
+            if let Some(scheme) = scheme.filter(|_| is_app_page(&url)) {
+                script.push_str(&fetch_bridge(&scheme, &markers.request));
+            }
+            let _ = view.evaluate_script(&script);
+        }
+    });
+
+    let cb_events = events.clone();
+    let cb_navigation = navigation.clone();
+    view.set_add_console_message_callback(
+        move |_view, _source, level, message, line, _column, source_id| {
+            let event = if let Some(message) = message.strip_prefix(&markers.message) {
+                ViewEvent::Message(message.to_string())
+            } else if let Some(link) = message.strip_prefix(&markers.link) {
+                cb_navigation.click(link);
+                return;
+            } else if let Some(dialog) = message.strip_prefix(&markers.dialog) {
+                match parse_dialog(dialog) {
+                    Some((dialog_id, dialog)) => ViewEvent::DialogRequested(dialog_id, dialog),
+                    None => return,
+                }
+            } else if let Some(request) = message.strip_prefix(&markers.request) {
+                match parse_request(request) {
+                    Some((request_id, request)) => ViewEvent::Request(request_id, request),
+                    None => return,
                 }
+            } else {
+                ViewEvent::ConsoleMessage(ConsoleMessage {
+                    level: match level {
+                        view::ConsoleMessageLevel::Log => ConsoleLevel::Log,
+                        view::ConsoleMessageLevel::Warning => ConsoleLevel::Warning,
+                        view::ConsoleMessageLevel::Error => ConsoleLevel::Error,
+                        view::ConsoleMessageLevel::Debug => ConsoleLevel::Debug,
+                        view::ConsoleMessageLevel::Info => ConsoleLevel::Info,
+                    },
+                    message,
+                    source: source_id,
+                    line,
+                })
+            };
+            if let Ok(mut events) = cb_events.lock() {
+                events.push((id, event));
+            }
+        },
+    );
+}
+
+/// Whether a page is the app's own, bundled under [`Assets::URL`] or loaded from html without a url,
+/// rather than a website that should not reach the app's scheme
+fn is_app_page(url: &str) -> bool {
+    url.is_empty() || url == "about:blank" || Assets::path(url).is_some()
+}
+
+/// Makes `fetch` send requests to the app's scheme over the bridge,
+/// they are answered by `window.iced.__respond` from [`Ultralight::respond`]
+///
+/// A request is sent as its id, method, url and base64 body on their own lines,
+/// followed by a line for each header
+fn fetch_bridge(scheme: &str, marker: &str) -> String {
+    format!(
+        r#"(() => {{
+            const log = console.log.bind(console);
+            const REQUEST = {marker};
+            const requests = new Map();
+            let nextRequest = 0;
+            window.iced.__respond = (id, status, headers, body) => {{
+                const resolve = requests.get(id);
+                requests.delete(id);
+                const bytes = Uint8Array.from(atob(body), (c) => c.charCodeAt(0));
+                const hasBody = ![101, 204, 205, 304].includes(status);
+                if (resolve) resolve(new Response(hasBody ? bytes : null, {{ status, headers }}));
+            }};
+            const fetch = window.fetch;
+            window.fetch = async (input, init = {{}}) => {{
+                const url = new URL(typeof input === "object" && "url" in input ? input.url : String(input), location.href);
+                if (url.protocol !== {scheme} + ":") return fetch(input, init);
+                const method = (init.method || input.method || "GET").toUpperCase();
+                const headers = new Headers(init.headers || input.headers || {{}});
+                const body = init.body == null
+                    ? new Uint8Array()
+                    : new Uint8Array(await new Response(init.body).arrayBuffer());
+                let binary = "";
+                for (const byte of body) binary += String.fromCharCode(byte);
+                const id = nextRequest++;
+                const lines = [id, method, url.href, btoa(binary)];
+                headers.forEach((value, name) => lines.push(name + ": " + value));
+                return new Promise((resolve) => {{
+                    requests.set(id, resolve);
+                    log(REQUEST + lines.join("\n"));
+                }});
+            }};
+        }})();"#,
+        scheme = js_string(scheme),
+        marker = js_string(marker),
+    )
+}
+
+/// Reads a dialog sent by the bridge, as its id, kind, default value and message on their own lines
+fn parse_dialog(dialog: &str) -> Option<(DialogId, Dialog)> {
+    let mut lines = dialog.splitn(4, '\n');
+    let id = lines.next()?.parse().ok()?;
+    let kind = lines.next()?;
+    let value = lines.next()?;
+    let kind = match kind {
+        "alert" => DialogKind::Alert,
+        "confirm" => DialogKind::Confirm,
+        "prompt" => DialogKind::Prompt(value.to_string()),
+        _ => return None,
+    };
+    let message = lines.next()?.to_string();
+    Some((id, Dialog { kind, message }))
+}
+
+/// Reads a request sent by [`fetch_bridge`]
+fn parse_request(request: &str) -> Option<(RequestId, Request)> {
+    let mut lines = request.split('\n');
+    let id = lines.next()?.parse().ok()?;
+    let method = lines.next()?.to_string();
+    let url = lines.next()?.to_string();
+    let body = BASE64_STANDARD.decode(lines.next()?).ok()?;
+    let headers = lines
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    Some((
+        id,
+        Request {
+            url,
+            method,
+            headers,
+            body,
+        },
+    ))
+}
+
+/// Quotes a string as a JavaScript string literal
+fn js_string(string: &str) -> String {
+    let mut literal = String::with_capacity(string.len() + 2);
+    literal.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            // control characters, and line separators older JavaScript engines reject in strings
+            c if c.is_control() || c == '\u{2028}' || c == '\u{2029}' => {
+                literal.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => literal.push(c),
         }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Finds Ultralight's resources directory, from the builder or else the environment
+fn resources_dir(resources_path: Option<PathBuf>) -> Result<PathBuf, Error> {
+    let resources_path = match resources_path {
+        Some(path) => path,
+        None => match var("ULTRALIGHT_RESOURCES_DIR") {
+            Ok(env) => PathBuf::from_str(&env).map_err(|_| {
+                Error::EngineInit(
+                    "Failed to get path from ultralight resources enviroment varible".to_string(),
+                )
+            })?,
+            Err(_) => {
+                // env not set - check if its been symlinked by build.rs
+                match Path::new("./resources").exists() {
+                    true => Path::new("./resources").to_owned(),
+                    false => return Err(Error::EngineInit(
+                        "ULTRALIGHT_RESOURCES_DIR was not set and ultralight-resources feature was not enabled".to_string(),
+                    )),
+                }
+            }
+        },
     };
-    assert!(Path::new(&resources_path).join("cacert.pem").exists());
-    assert!(Path::new(&resources_path).join("icudt67l.dat").exists());
-    resources_path
-        .parent() // leaves resources directory
-        .expect("resources path needs to point to the resources directory")
-        .into()
+    for resource in ["cacert.pem", "icudt67l.dat"] {
+        if !Path::new(&resources_path).join(resource).exists() {
+            return Err(Error::EngineInit(format!(
+                "Ultralight resource {resource} was not found in {}",
+                resources_path.display()
+            )));
+        }
+    }
+    Ok(resources_path)
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1064,3 +2323,118 @@ fn iced_key_to_ultralight_key(
 
     event::KeyEvent::new(creation_info).ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "https://example.com/";
+    const NEXT: &str = "https://example.com/next";
+
+    fn approve(url: &str) -> GateState {
+        GateState {
+            approved: Some(Approval::Url(url.to_string())),
+            ..GateState::default()
+        }
+    }
+
+    #[test]
+    fn page_navigations_are_held_back() {
+        let mut gate = GateState::default();
+        assert_eq!(gate.begin(NEXT, true), Some(NavigationKind::Redirect));
+        // stopping it fails the load, which is not reported
+        assert!(!gate.fail(NEXT));
+
+        gate.clicked_link = Some(NEXT.to_string());
+        assert_eq!(gate.begin(NEXT, true), Some(NavigationKind::LinkClick));
+        assert_eq!(gate.begin(NEXT, false), None);
+    }
+
+    #[test]
+    fn refresh_does_not_let_page_navigations_through() {
+        let mut gate = approve(PAGE);
+        // Ultralight reports the url normalized
+        assert_eq!(gate.begin("https://example.com", true), None);
+        gate.finish(PAGE);
+
+        assert_eq!(gate.begin(NEXT, true), Some(NavigationKind::Redirect));
+    }
+
+    #[test]
+    fn approval_only_covers_its_url() {
+        let mut gate = approve(PAGE);
+        assert_eq!(
+            gate.begin("https://example.com/elsewhere", true),
+            Some(NavigationKind::Redirect)
+        );
+        assert_eq!(gate.begin(PAGE, true), None);
+    }
+
+    #[test]
+    fn history_approval_ends_with_the_history_change() {
+        let mut gate = GateState {
+            approved: Some(Approval::Any),
+            ..GateState::default()
+        };
+        // a history navigation served without beginning a new load
+        gate.history_changed();
+
+        assert_eq!(gate.begin(NEXT, true), Some(NavigationKind::Redirect));
+    }
+
+    #[test]
+    fn approval_ends_with_a_load_that_failed_before_it_began() {
+        let mut gate = approve(NEXT);
+        assert!(gate.fail(NEXT));
+
+        assert_eq!(gate.begin(NEXT, true), Some(NavigationKind::Redirect));
+    }
+
+    #[test]
+    fn replaced_loads_keep_the_apps_approval() {
+        let mut gate = GateState::default();
+        assert_eq!(gate.begin(PAGE, false), None);
+
+        // the app navigates while the page is still loading, which cancels it
+        gate.approved = Some(Approval::Url(NEXT.to_string()));
+        assert!(gate.fail(PAGE));
+        assert_eq!(gate.begin(NEXT, true), None);
+
+        gate.approved = Some(Approval::Any);
+        gate.finish(NEXT);
+        assert_eq!(gate.begin(PAGE, true), None);
+    }
+
+    #[test]
+    fn held_navigations_only_swallow_their_own_failure() {
+        let mut gate = GateState::default();
+        assert_eq!(gate.begin(NEXT, true), Some(NavigationKind::Redirect));
+        // stopping it did not fail it, and the page goes on to fail loading something else
+        assert!(gate.fail(PAGE));
+
+        assert_eq!(gate.begin(NEXT, true), Some(NavigationKind::Redirect));
+        assert_eq!(gate.begin(PAGE, false), None);
+        // the held navigation is over once the next one begins
+        assert!(gate.fail(NEXT));
+    }
+
+    #[test]
+    fn every_view_marks_its_bridge_differently() {
+        let (first, second) = (Markers::new(), Markers::new());
+        assert_ne!(first.message, second.message);
+
+        let markers = [&first.message, &first.request, &first.dialog, &first.link];
+        for (index, marker) in markers.iter().enumerate() {
+            assert!(marker.starts_with("__iced_webview_"));
+            assert!(markers[index + 1..].iter().all(|other| other != marker));
+        }
+    }
+
+    #[test]
+    fn only_the_apps_own_pages_reach_its_scheme() {
+        assert!(is_app_page("file:///app/index.html"));
+        assert!(is_app_page("about:blank"));
+        assert!(!is_app_page("https://example.com/"));
+        assert!(!is_app_page("file:///home/user/page.html"));
+    }
+}
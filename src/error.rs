@@ -0,0 +1,46 @@
+use std::fmt;
+
+use crate::ViewId;
+
+/// Errors that can be returned by an [`Engine`](crate::Engine) or the webview widgets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The requested View id does not exist, likely because it was already closed
+    UnknownView(ViewId),
+    /// The basic webview was asked for a view index that does not exist
+    UnknownViewIndex(u32),
+    /// The basic webview has no current view set. Call `Action::ChangeView` first
+    NoCurrentView,
+    /// The browser engine failed to load the requested page
+    LoadFailed(String),
+    /// The browser engine failed to start
+    EngineInit(String),
+    /// The system clipboard could not be accessed
+    ClipboardUnavailable(String),
+    /// A captured image could not be encoded
+    EncodeFailed(String),
+    /// Browsing data could not be removed from a session
+    ClearDataFailed(String),
+    /// The engine can not do what was asked of it
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownView(id) => write!(f, "the view id {id} does not exist"),
+            Error::UnknownViewIndex(index) => write!(f, "the view index {index} does not exist"),
+            Error::NoCurrentView => write!(f, "there is no current view set"),
+            Error::LoadFailed(reason) => write!(f, "failed to load page: {reason}"),
+            Error::EngineInit(reason) => write!(f, "failed to start browser engine: {reason}"),
+            Error::ClipboardUnavailable(reason) => {
+                write!(f, "the clipboard is unavailable: {reason}")
+            }
+            Error::EncodeFailed(reason) => write!(f, "failed to encode image: {reason}"),
+            Error::ClearDataFailed(reason) => write!(f, "failed to clear browsing data: {reason}"),
+            Error::Unsupported(reason) => write!(f, "not supported by the engine: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,352 @@
+use iced::widget::{button, row, text, text_input};
+use iced::{event, keyboard, window, Alignment, Element, Event, Length, Subscription, Task, Theme};
+
+use crate::{advanced, basic, FindResult, ViewId};
+
+/// A find in page bar that works with both the basic and the advanced webview
+///
+/// It keeps the query and the latest result, and turns what the user does into finds for the webview
+/// ```rust
+/// use iced_webview::{find_bar, Engine, FindBar, WebView};
+///
+/// #[derive(Debug, Clone)]
+/// enum Message {
+///     WebView(iced_webview::Action),
+///     FindBar(find_bar::Message),
+/// }
+///
+/// fn update(find_bar: &mut FindBar, message: find_bar::Message) -> iced::Task<Message> {
+///     find_bar.update(message, |request| Message::WebView(request.basic()))
+/// }
+///
+/// fn webview<E: Engine>(webview: WebView<E, Message>) -> WebView<E, Message> {
+///     webview.on_find_result(|result| Message::FindBar(find_bar::Message::Found(result)))
+/// }
+/// ```
+#[derive(Debug)]
+pub struct FindBar {
+    query: String,
+    case_sensitive: bool,
+    active_match: usize,
+    total_matches: usize,
+    is_open: bool,
+    input: text_input::Id,
+}
+
+/// Messages handled by [`FindBar::update`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// Shows the bar and focuses its input
+    Open,
+    /// Hides the bar and stops finding
+    Close,
+    /// The query was edited, which finds it from the start
+    QueryChanged(String),
+    /// Toggles whether letters have to match in case
+    ToggleCaseSensitive,
+    /// Selects the next match
+    Next,
+    /// Selects the previous match
+    Previous,
+    /// The outcome of a find, as reported to `on_find_result`
+    ///
+    /// Results of earlier queries, which can arrive after the query was edited, are ignored
+    Found(FindResult),
+}
+
+/// What the [`FindBar`] asks of the webview
+#[derive(Debug, Clone, PartialEq)]
+pub enum FindRequest {
+    /// Find the query, selecting the next or previous match
+    Find {
+        /// The text to find
+        query: String,
+        /// Whether letters have to match in case
+        case_sensitive: bool,
+        /// Whether to select the next match rather than the previous one
+        forward: bool,
+    },
+    /// Stop finding, clearing the selected match
+    Stop,
+}
+
+impl FindRequest {
+    /// The request as an action for the basic webview's current view
+    pub fn basic(self) -> basic::Action {
+        match self {
+            FindRequest::Find {
+                query,
+                case_sensitive,
+                forward,
+            } => basic::Action::Find {
+                query,
+                case_sensitive,
+                forward,
+            },
+            FindRequest::Stop => basic::Action::StopFinding,
+        }
+    }
+
+    /// The request as an action for one of the advanced webview's views
+    pub fn advanced(self, view: ViewId) -> advanced::Action {
+        match self {
+            FindRequest::Find {
+                query,
+                case_sensitive,
+                forward,
+            } => advanced::Action::Find {
+                view,
+                query,
+                case_sensitive,
+                forward,
+            },
+            FindRequest::Stop => advanced::Action::StopFinding(view),
+        }
+    }
+}
+
+impl Default for FindBar {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            case_sensitive: false,
+            active_match: 0,
+            total_matches: 0,
+            is_open: false,
+            input: text_input::Id::unique(),
+        }
+    }
+}
+
+impl FindBar {
+    /// Create a closed find bar
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the bar is shown, so the app knows when to put [`FindBar::view`] on screen
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    /// Handle a message, turning the finds it causes into the app's messages with `to_message`
+    pub fn update<T: Send + 'static>(
+        &mut self,
+        message: Message,
+        to_message: impl Fn(FindRequest) -> T,
+    ) -> Task<T> {
+        match message {
+            Message::Open => {
+                self.is_open = true;
+                Task::batch([
+                    text_input::focus(self.input.clone()),
+                    text_input::select_all(self.input.clone()),
+                ])
+            }
+            Message::Close => {
+                if !self.is_open {
+                    return Task::none();
+                }
+                self.is_open = false;
+                self.clear_count();
+                Task::done(to_message(FindRequest::Stop))
+            }
+            Message::QueryChanged(query) => {
+                self.query = query;
+                self.clear_count();
+                self.find(true, to_message)
+            }
+            Message::ToggleCaseSensitive => {
+                self.case_sensitive = !self.case_sensitive;
+                self.clear_count();
+                self.find(true, to_message)
+            }
+            Message::Next => self.find(true, to_message),
+            Message::Previous => self.find(false, to_message),
+            Message::Found(result) => {
+                if result.query == self.query && result.case_sensitive == self.case_sensitive {
+                    self.active_match = result.active_match;
+                    self.total_matches = result.total_matches;
+                }
+                Task::none()
+            }
+        }
+    }
+
+    fn find<T: Send + 'static>(
+        &mut self,
+        forward: bool,
+        to_message: impl Fn(FindRequest) -> T,
+    ) -> Task<T> {
+        if self.query.is_empty() {
+            return Task::done(to_message(FindRequest::Stop));
+        }
+        Task::done(to_message(FindRequest::Find {
+            query: self.query.clone(),
+            case_sensitive: self.case_sensitive,
+            forward,
+        }))
+    }
+
+    /// The bar itself, with the query, the match count and buttons to step through matches
+    pub fn view(&self) -> Element<'_, Message> {
+        let case_style: fn(&Theme, button::Status) -> button::Style = if self.case_sensitive {
+            button::primary
+        } else {
+            button::secondary
+        };
+
+        row![
+            text_input("Find in page", &self.query)
+                .id(self.input.clone())
+                .on_input(Message::QueryChanged)
+                .on_submit(Message::Next)
+                .width(Length::Fixed(240.)),
+            text(self.count()),
+            button(text("Aa"))
+                .on_press(Message::ToggleCaseSensitive)
+                .style(case_style),
+            button(text("Previous")).on_press(Message::Previous),
+            button(text("Next")).on_press(Message::Next),
+            button(text("Close")).on_press(Message::Close),
+        ]
+        .spacing(5)
+        .padding(5)
+        .align_y(Alignment::Center)
+        .into()
+    }
+
+    /// Forgets the count of a search that no longer applies, until the new one reports back
+    fn clear_count(&mut self) {
+        self.active_match = 0;
+        self.total_matches = 0;
+    }
+
+    /// How many matches there are, and which one is selected
+    fn count(&self) -> String {
+        if self.query.is_empty() {
+            String::new()
+        } else if self.total_matches == 0 {
+            "No matches".to_string()
+        } else {
+            format!("{} of {}", self.active_match, self.total_matches)
+        }
+    }
+
+    /// Opens the bar with Ctrl+F (Cmd+F on macOS), and closes it with Escape while it is open
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.is_open {
+            event::listen_with(open_or_close)
+        } else {
+            event::listen_with(open)
+        }
+    }
+}
+
+fn open(event: Event, _status: event::Status, _window: window::Id) -> Option<Message> {
+    match event {
+        Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. })
+            if modifiers.command() && key.as_ref() == keyboard::Key::Character("f") =>
+        {
+            Some(Message::Open)
+        }
+        _ => None,
+    }
+}
+
+fn open_or_close(event: Event, status: event::Status, window: window::Id) -> Option<Message> {
+    match event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key: keyboard::Key::Named(keyboard::key::Named::Escape),
+            ..
+        }) => Some(Message::Close),
+        event => open(event, status, window),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::webview::messages;
+
+    fn update(find_bar: &mut FindBar, message: Message) -> Vec<FindRequest> {
+        messages(find_bar.update(message, |request| request))
+    }
+
+    fn find(query: &str, forward: bool) -> FindRequest {
+        FindRequest::Find {
+            query: query.to_string(),
+            case_sensitive: false,
+            forward,
+        }
+    }
+
+    fn found(query: &str, active_match: usize, total_matches: usize) -> Message {
+        Message::Found(FindResult {
+            query: query.to_string(),
+            case_sensitive: false,
+            active_match,
+            total_matches,
+        })
+    }
+
+    #[test]
+    fn opening_and_closing() {
+        let mut find_bar = FindBar::new();
+        assert_eq!(update(&mut find_bar, Message::Close), []);
+
+        assert_eq!(update(&mut find_bar, Message::Open), []);
+        assert!(find_bar.is_open());
+        assert_eq!(update(&mut find_bar, Message::Close), [FindRequest::Stop]);
+        assert!(!find_bar.is_open());
+    }
+
+    #[test]
+    fn typing_finds_the_query() {
+        let mut find_bar = FindBar::new();
+        let _ = update(&mut find_bar, Message::Open);
+
+        let query = Message::QueryChanged("iced".to_string());
+        assert_eq!(update(&mut find_bar, query), [find("iced", true)]);
+        assert_eq!(
+            update(&mut find_bar, Message::Previous),
+            [find("iced", false)]
+        );
+        assert_eq!(
+            update(&mut find_bar, Message::QueryChanged(String::new())),
+            [FindRequest::Stop]
+        );
+    }
+
+    #[test]
+    fn results_of_earlier_queries_are_ignored() {
+        let mut find_bar = FindBar::new();
+        let _ = update(&mut find_bar, Message::QueryChanged("ice".to_string()));
+        let _ = update(&mut find_bar, Message::QueryChanged("iced".to_string()));
+
+        let _ = update(&mut find_bar, found("iced", 1, 2));
+        let _ = update(&mut find_bar, found("ice", 3, 7));
+        assert_eq!(find_bar.count(), "1 of 2");
+
+        let _ = update(&mut find_bar, Message::ToggleCaseSensitive);
+        let _ = update(&mut find_bar, found("iced", 2, 2));
+        assert_eq!(find_bar.count(), "No matches");
+    }
+
+    #[test]
+    fn requests_become_actions() {
+        let view = 3;
+        assert!(matches!(
+            find("iced", true).advanced(view),
+            advanced::Action::Find {
+                view: 3,
+                forward: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            FindRequest::Stop.basic(),
+            basic::Action::StopFinding
+        ));
+    }
+}
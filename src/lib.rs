@@ -1,6 +1,6 @@
 //! Iced_webview is a library to embed web views in iced applications. It is a renderer agnostic webview library for Iced.
 //!
-//! > Note: Currently this library only supports [Ultralight](https://ultralig.ht)/Webkit, but more rendering engines are planned to be supported.
+//! > Note: Currently this library supports [Ultralight](https://ultralig.ht)/Webkit, and a small pure Rust engine for local html behind the `pure-html` feature. More rendering engines are planned to be supported.
 //! > [Ultralight has its own license](https://ultralig.ht/pricing/) that should be reviewed before deciding if it works for you
 //!
 //! Has two separate widgets: Basic, and Advanced.
@@ -20,16 +20,21 @@
 //!
 //! Create a new struct to store webview state
 //! ```rust
+//! # #[cfg(feature = "ultralight")]
+//! # mod example {
 //! struct State {
 //!    webview: iced_webview::WebView<iced_webview::Ultralight, Message>,
 //! }
 //! # #[derive(Clone)]
 //! # enum Message { }
+//! # }
 //! ```
 //!
 //! ### Then you should be able to call the usual `view/update` methods:
 //!
 //! ```rust
+//! # #[cfg(feature = "ultralight")]
+//! # mod example {
 //! fn update(state: &mut State, message: Message) -> iced::Task<Message> {
 //!     match message {
 //!         Message::WebView(msg) => state.webview.update(msg),
@@ -39,62 +44,101 @@
 //! # #[derive(Clone)]
 //! # enum Message { WebView(iced_webview::Action), Update }
 //! # struct State { webview: iced_webview::WebView<iced_webview::Ultralight, Message> }
+//! # }
 //! ```
 //!
 //! ```rust
+//! # #[cfg(feature = "ultralight")]
+//! # mod example {
 //! fn view(state: &mut State, message: Message) -> iced::Element<Message> {
 //!    state.webview.view().map(Message::WebView).into()
 //! }
 //! # #[derive(Clone)]
 //! # enum Message { WebView(iced_webview::Action) }
 //! # struct State { webview: iced_webview::WebView<iced_webview::Ultralight, Message> }
+//! # }
 //! ```
 //!
-//! The subscription provides periodic updates so that all the backend rendering is done frequently enough
+//! The subscription keeps the backend rendering, updating quickly only while a page is loading or animating
 //!
 //! ```rust
-//! use iced::time;
+//! # #[cfg(feature = "ultralight")]
+//! # mod example {
 //! fn subscription(state: &mut State) -> iced::Subscription<Message> {
-//!     time::every(std::time::Duration::from_millis(10))
-//!         .map(|_| iced_webview::Action::Update)
-//!         .map(Message::WebView)
+//!     state.webview.subscription().map(Message::WebView)
 //! }
 //! # #[derive(Clone)]
 //! # enum Message { WebView(iced_webview::Action) }
 //! # struct State { webview: iced_webview::WebView<iced_webview::Ultralight, Message> }
+//! # }
 //! ```
 //!
 //!
 //! Examples can be found in the [iced_webview repo](https://github.com/LegitCamper/iced_webview/tree/main/examples)
 //!
+use iced::advanced::image::Bytes;
 use iced::widget::image;
+use iced::Rectangle;
 
 /// Engine Trait and Engine implementations
 pub mod engines;
-pub use engines::{Engine, PageType, PixelFormat, ViewId};
+pub use engines::threaded::Threaded;
+pub use engines::{
+    BrowsingData, ConsoleLevel, ConsoleMessage, Dialog, DialogAnswer, DialogId, DialogKind, Engine,
+    FindResult, LoadFailure, NavigationKind, Opener, PageType, PixelFormat, Request, RequestId,
+    Response, ScriptResult, Session, ViewEvent, ViewId,
+};
+
+mod assets;
+pub use assets::Assets;
+
+mod error;
+pub use error::Error;
+
+/// A find in page bar for either webview
+pub mod find_bar;
+pub use find_bar::FindBar;
 
 mod webview;
 pub use basic::{Action, WebView};
 pub use webview::{advanced, basic}; // pub these since its the default/reccommended method
+pub use webview::{window_scale_factor, DialogRequest, DialogResponder, NavigationDecision};
 
 #[cfg(feature = "ultralight")]
-pub use engines::ultralight::Ultralight;
+pub use engines::ultralight::{Ultralight, UltralightBuilder};
+
+#[cfg(feature = "mock")]
+pub use engines::mock::Mock;
+
+#[cfg(feature = "pure-html")]
+pub use engines::pure_html::PureHtml;
 
 /// Image details for passing the view around
-#[derive(Clone, Debug, PartialEq)]
+///
+/// Its pixels are RGBA, row by row, at the size the view was rendered in physical pixels.
+/// Views captured with `Action::CaptureView` arrive as one.
+/// Clones share their pixels, so cloning is cheap
+#[derive(Clone, Debug)]
 pub struct ImageInfo {
-    pixels: Vec<u8>,
+    pixels: Bytes,
     width: u32,
     height: u32,
+    // built once, so showing the same frame again doesn't upload it to the gpu again
+    handle: image::Handle,
 }
 
 impl Default for ImageInfo {
     fn default() -> Self {
-        Self {
-            pixels: vec![255; (Self::WIDTH as usize * Self::HEIGHT as usize) * 4],
-            width: Self::WIDTH,
-            height: Self::HEIGHT,
-        }
+        Self::blank(Self::WIDTH, Self::HEIGHT)
+    }
+}
+
+impl PartialEq for ImageInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_pixels(other)
+            || (self.width == other.width
+                && self.height == other.height
+                && self.pixels == other.pixels)
     }
 }
 
@@ -118,28 +162,96 @@ impl ImageInfo {
             }
         };
 
+        Self::from_rgba(pixels, width, height)
+    }
+
+    fn from_rgba(pixels: Vec<u8>, width: u32, height: u32) -> Self {
+        let pixels = Bytes::from(pixels);
         Self {
+            handle: image::Handle::from_rgba(width, height, pixels.clone()),
             pixels,
             width,
             height,
         }
     }
 
+    /// Whether both are clones of the same image, which is much cheaper than comparing their pixels
+    fn same_pixels(&self, other: &Self) -> bool {
+        self.handle.id() == other.handle.id()
+    }
+
+    /// The width in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The RGBA pixels, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Copies the part of the image inside an area in pixels, like a single element of the page
+    ///
+    /// The area is clipped to the image, so it may come out smaller than asked for
+    pub fn crop(&self, area: Rectangle<u32>) -> Self {
+        let x = area.x.min(self.width);
+        let y = area.y.min(self.height);
+        let width = area.width.min(self.width - x);
+        let height = area.height.min(self.height - y);
+        if width == 0 || height == 0 {
+            return Self::blank(width, height);
+        }
+
+        let row_len = self.width as usize * 4;
+        let pixels = self
+            .pixels
+            .chunks_exact(row_len)
+            .skip(y as usize)
+            .take(height as usize)
+            .flat_map(|row| &row[x as usize * 4..(x + width) as usize * 4])
+            .copied()
+            .collect();
+        Self::from_rgba(pixels, width, height)
+    }
+
+    /// Encodes the image as a PNG file, to save it or attach it to a bug report
+    pub fn to_png(&self) -> Result<Vec<u8>, Error> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(|err| Error::EncodeFailed(err.to_string()))?;
+        Ok(png)
+    }
+
+    /// Converts the image into a handle iced's `image` widget can show, like a thumbnail of a view
+    ///
+    /// Every call returns the same handle, so iced only uploads the image once
+    pub fn to_handle(&self) -> image::Handle {
+        self.handle.clone()
+    }
+
     fn as_image(&self) -> image::Image<image::Handle> {
-        image::Image::new(image::Handle::from_rgba(
-            self.width,
-            self.height,
-            self.pixels.clone(),
-        ))
-        .content_fit(iced::ContentFit::ScaleDown)
-        .filter_method(image::FilterMethod::Nearest)
+        // frames are rendered at the widget's physical size, so filling it is at most a pixel off,
+        // which linear filtering keeps smooth where nearest would drop or double whole rows
+        image::Image::new(self.to_handle())
+            .content_fit(iced::ContentFit::Fill)
+            .filter_method(image::FilterMethod::Linear)
     }
 
     fn blank(width: u32, height: u32) -> Self {
-        Self {
-            pixels: vec![255; (width as usize * height as usize) * 4],
+        Self::from_rgba(
+            vec![255; (width as usize * height as usize) * 4],
             width,
             height,
-        }
+        )
     }
 }
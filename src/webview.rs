@@ -5,3 +5,205 @@ pub mod advanced;
 /// Basic allows users to have simple interfaces like close current and
 /// allows users to index views by ints like 0, 1 , or 2
 pub mod basic;
+
+use iced::keyboard;
+use iced::mouse::{self, ScrollDelta};
+use iced::{window, Point, Rectangle, Size, Task};
+use std::sync::Arc;
+
+use crate::{Dialog, DialogId, DialogKind, Request, Response, ViewId};
+
+/// Answers requests to the app's scheme, routing each response back into the webview
+struct SchemeHandler<Action, Message> {
+    handle: Box<dyn Fn(Request) -> Task<Response>>,
+    to_message: Arc<dyn Fn(Action) -> Message + Send + Sync>,
+}
+
+impl<Action: 'static, Message: Send + 'static> SchemeHandler<Action, Message> {
+    fn new(
+        handle: impl Fn(Request) -> Task<Response> + 'static,
+        to_message: impl Fn(Action) -> Message + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            handle: Box::new(handle),
+            to_message: Arc::new(to_message),
+        }
+    }
+
+    /// Runs the handler, turning its response into the action that delivers it
+    fn handle(
+        &self,
+        request: Request,
+        respond: impl Fn(Response) -> Action + Send + 'static,
+    ) -> Task<Message> {
+        let to_message = self.to_message.clone();
+        (self.handle)(request).map(move |response| to_message(respond(response)))
+    }
+}
+
+/// What to do with a navigation, decided by the webview's `on_navigation_request`
+#[derive(Clone, Debug, PartialEq)]
+pub enum NavigationDecision<Message> {
+    /// Lets the view navigate
+    Allow,
+    /// Keeps the view on its current page
+    Cancel,
+    /// Keeps the view on its current page, and opens the url in a new view instead
+    OpenInNewView,
+    /// Keeps the view on its current page, and sends the message to the app,
+    /// which handles the url itself, for example by opening it in the system browser
+    HandOff(Message),
+}
+
+/// A JavaScript dialog a page opened, reported to the webview's `on_dialog_request`
+///
+/// Show it however suits the app, like with a modal,
+/// and answer it by sending `Action::AnswerDialog` with its responder
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialogRequest {
+    /// Which kind of dialog it is
+    pub kind: DialogKind,
+    /// The text the page shows in the dialog
+    pub message: String,
+    /// Identifies the dialog when answering it
+    pub responder: DialogResponder,
+}
+
+/// Identifies the dialog an `Action::AnswerDialog` answers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DialogResponder {
+    view: ViewId,
+    dialog: DialogId,
+}
+
+impl DialogRequest {
+    fn new(view: ViewId, dialog: DialogId, Dialog { kind, message }: Dialog) -> Self {
+        Self {
+            kind,
+            message,
+            responder: DialogResponder { view, dialog },
+        }
+    }
+}
+
+/// Gets the scale factor of the app's most recently opened window,
+/// to send to the webview with `Action::SetScaleFactor`
+pub fn window_scale_factor() -> Task<f32> {
+    window::get_latest().and_then(window::get_scale_factor)
+}
+
+// The zoom levels `ZoomIn` and `ZoomOut` step through, like in browsers
+const ZOOM_LEVELS: [f32; 15] = [
+    0.25, 0.33, 0.5, 0.67, 0.75, 0.8, 0.9, 1.0, 1.1, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0,
+];
+
+/// Keeps a zoom between the smallest and largest zoom levels
+fn clamp_zoom(zoom: f32) -> f32 {
+    zoom.clamp(ZOOM_LEVELS[0], ZOOM_LEVELS[ZOOM_LEVELS.len() - 1])
+}
+
+/// The next zoom level above a zoom
+fn zoom_in(zoom: f32) -> f32 {
+    ZOOM_LEVELS
+        .into_iter()
+        .find(|level| *level > zoom + f32::EPSILON)
+        .unwrap_or(clamp_zoom(zoom))
+}
+
+/// The next zoom level below a zoom
+fn zoom_out(zoom: f32) -> f32 {
+    ZOOM_LEVELS
+        .into_iter()
+        .rev()
+        .find(|level| *level < zoom - f32::EPSILON)
+        .unwrap_or(clamp_zoom(zoom))
+}
+
+/// A zoom shortcut the widgets handle themselves once `zoom_shortcuts` is enabled,
+/// turned into each webview's own zoom actions
+enum ZoomShortcut {
+    In,
+    Out,
+    Reset,
+}
+
+impl ZoomShortcut {
+    /// Like in browsers, Ctrl (Cmd on macOS) with + or = zooms in, with - zooms out and with 0 resets
+    fn from_key(event: &keyboard::Event) -> Option<Self> {
+        let keyboard::Event::KeyPressed { key, modifiers, .. } = event else {
+            return None;
+        };
+        if !modifiers.command() {
+            return None;
+        }
+        match key.as_ref() {
+            keyboard::Key::Character("+" | "=") => Some(Self::In),
+            keyboard::Key::Character("-") => Some(Self::Out),
+            keyboard::Key::Character("0") => Some(Self::Reset),
+            _ => None,
+        }
+    }
+
+    /// Ctrl (Cmd on macOS) with the mouse wheel zooms in when scrolled up and out when scrolled down
+    fn from_wheel(delta: ScrollDelta, modifiers: keyboard::Modifiers) -> Option<Self> {
+        if !modifiers.command() {
+            return None;
+        }
+        let (ScrollDelta::Lines { y, .. } | ScrollDelta::Pixels { y, .. }) = delta;
+        if y > 0.0 {
+            Some(Self::In)
+        } else if y < 0.0 {
+            Some(Self::Out)
+        } else {
+            None
+        }
+    }
+}
+
+/// The size in physical pixels of a widget's logical bounds
+fn physical_size(bounds: Rectangle, scale_factor: f32) -> Size<u32> {
+    Size::new(
+        (bounds.width * scale_factor).round() as u32,
+        (bounds.height * scale_factor).round() as u32,
+    )
+}
+
+/// Maps a mouse event and its position within a widget from logical to physical pixels
+fn to_physical(event: mouse::Event, point: Point, scale_factor: f32) -> (mouse::Event, Point) {
+    let event = match event {
+        mouse::Event::WheelScrolled {
+            delta: ScrollDelta::Pixels { x, y },
+        } => mouse::Event::WheelScrolled {
+            delta: ScrollDelta::Pixels {
+                x: x * scale_factor,
+                y: y * scale_factor,
+            },
+        },
+        event => event,
+    };
+    (
+        event,
+        Point::new(point.x * scale_factor, point.y * scale_factor),
+    )
+}
+
+/// Runs a task returned by a widget's `update`, collecting the messages it produces
+#[cfg(test)]
+pub(crate) fn messages<T>(task: Task<T>) -> Vec<T> {
+    use iced::futures::{executor, future, StreamExt};
+    use iced_runtime::Action;
+
+    let Some(stream) = iced_runtime::task::into_stream(task) else {
+        return Vec::new();
+    };
+    executor::block_on(
+        stream
+            .filter_map(|action| {
+                future::ready(match action {
+                    Action::Output(message) => Some(message),
+                    _ => None,
+                })
+            })
+            .collect(),
+    )
+}
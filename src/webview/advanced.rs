@@ -3,73 +3,208 @@ use iced::advanced::{
     graphics::core::event,
     layout,
     renderer::{self},
-    widget::Tree,
+    widget::tree::{self, Tree},
     Clipboard, Layout, Shell, Widget,
 };
 use iced::event::Status;
 use iced::keyboard;
 use iced::mouse::{self, Interaction};
 use iced::widget::image::{Handle, Image};
-use iced::{Element, Point, Size, Task};
+use iced::{time, Element, Point, Size, Subscription, Task};
 use iced::{Event, Length, Rectangle};
+use std::time::Duration;
 use url::Url;
 
-use crate::{engines, ImageInfo, PageType, ViewId};
+use super::{
+    clamp_zoom, physical_size, to_physical, zoom_in, zoom_out, DialogRequest, DialogResponder,
+    NavigationDecision, SchemeHandler, ZoomShortcut,
+};
+use crate::{
+    engines, Assets, BrowsingData, ConsoleMessage, Dialog, DialogAnswer, DialogId, Error,
+    FindResult, ImageInfo, LoadFailure, NavigationKind, Opener, PageType, Request, RequestId,
+    Response, ScriptResult, Session, ViewEvent, ViewId,
+};
 
 #[allow(missing_docs)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Action {
     CloseView(ViewId),
-    CreateView(PageType),
+    /// Creates a view in a session, views in different sessions do not share cookies or storage
+    CreateView(PageType, Session),
+    /// Focuses a view so it receives keyboard input, sent by its widget when clicked
+    Focus(ViewId),
+    /// Unfocuses a view, sent by its widget when something else is clicked
+    Unfocus(ViewId),
     GoBackward(ViewId),
     GoForward(ViewId),
     GoToUrl(ViewId, Url),
     Refresh(ViewId),
     SendKeyboardEvent(ViewId, keyboard::Event),
     SendMouseEvent(ViewId, mouse::Event, Point),
+    /// Runs JavaScript in a view, its outcome is reported to `on_script_result`
+    EvaluateJavaScript(ViewId, String),
+    /// Sends a message to a view's page, where it arrives as a `message` event on `window.iced`
+    PostMessage(ViewId, String),
+    /// Answers a dialog reported to `on_dialog_request`
+    AnswerDialog(DialogResponder, DialogAnswer),
+    /// Removes data views stored in a session, or in every session when none is given,
+    /// then sends `on_data_cleared`, or `on_error` if the engine could not clear it
+    ClearData(Option<Session>, BrowsingData),
+    /// Answers a page's request to the app's scheme, sent by the webview once its handler finishes
+    Respond(ViewId, RequestId, Response),
     /// Call this periodically to update a view
     Update(ViewId),
     /// Call this periodically to update a view(s)
     UpdateAll,
-    Resize(Size<u32>),
+    /// Resizes a specific view to a size in physical pixels, sent by its widget when the widget's bounds change
+    Resize(ViewId, Size<u32>),
+    /// Draws every view at the window's scale factor, so pages stay sharp on HiDPI displays
+    ///
+    /// Get it with [`window_scale_factor`](crate::window_scale_factor),
+    /// multiplied by the app's own scale factor if it sets one
+    SetScaleFactor(f32),
+    /// Zooms a view's page, where 1.0 shows it at its normal size
+    SetZoom(ViewId, f32),
+    /// Zooms a view in by one step, like Ctrl and + in browsers
+    ZoomIn(ViewId),
+    /// Zooms a view out by one step, like Ctrl and - in browsers
+    ZoomOut(ViewId),
+    /// Shows a view's page at its normal size again, like Ctrl and 0 in browsers
+    ResetZoom(ViewId),
+    /// Renders a view and sends a copy of it to `on_capture`, at its size in physical pixels
+    CaptureView(ViewId),
+    /// Renders a view's whole page, including what is scrolled out of view,
+    /// and sends it to `on_capture` as one image once it is done
+    CaptureFullPage(ViewId),
+    /// Finds text in a view's page and selects the next match, or the previous one
+    /// when not `forward`, reporting the result to `on_find_result`
+    ///
+    /// Finding the same query again moves on from the selected match
+    Find {
+        view: ViewId,
+        query: String,
+        case_sensitive: bool,
+        forward: bool,
+    },
+    /// Stops finding in a view, clearing the selected match
+    StopFinding(ViewId),
 }
 
+// How often the engine is updated while a view is busy, roughly once a frame
+const BUSY_TICK: Duration = Duration::from_millis(16);
+// How often the engine is updated while every view is idle
+const IDLE_TICK: Duration = Duration::from_secs(1);
+
+type NavigationHook<Message> =
+    Box<dyn Fn(ViewId, Url, NavigationKind) -> NavigationDecision<Message>>;
+type NewWindowCallback<Message> = Box<dyn Fn(ViewId, Url, Opener) -> Message>;
+
 /// The Advanced WebView widget that creates and shows webview(s)
 pub struct WebView<Engine, Message>
 where
     Engine: engines::Engine,
 {
     engine: Engine,
-    view_size: Size<u32>,
+    view_sizes: Vec<(ViewId, Size<u32>)>,
+    scale_factor: f32,
     on_close_view: Option<Box<dyn Fn(ViewId) -> Message>>,
     on_create_view: Option<Box<dyn Fn(ViewId) -> Message>>,
     on_url_change: Option<Box<dyn Fn(ViewId, String) -> Message>>,
     urls: Vec<(ViewId, String)>,
     on_title_change: Option<Box<dyn Fn(ViewId, String) -> Message>>,
     titles: Vec<(ViewId, String)>,
+    on_load_start: Option<Box<dyn Fn(ViewId, String) -> Message>>,
+    on_dom_ready: Option<Box<dyn Fn(ViewId, String) -> Message>>,
+    on_load_finish: Option<Box<dyn Fn(ViewId, String) -> Message>>,
+    on_load_fail: Option<Box<dyn Fn(ViewId, LoadFailure) -> Message>>,
+    on_history_change: Option<Box<dyn Fn(ViewId) -> Message>>,
+    on_script_result: Option<Box<dyn Fn(ViewId, ScriptResult) -> Message>>,
+    on_message: Option<Box<dyn Fn(ViewId, String) -> Message>>,
+    on_console_message: Option<Box<dyn Fn(ViewId, ConsoleMessage) -> Message>>,
+    #[cfg(feature = "log")]
+    log_console_messages: bool,
+    scheme_handler: Option<SchemeHandler<Action, Message>>,
+    on_navigation_request: Option<NavigationHook<Message>>,
+    on_dialog_request: Option<Box<dyn Fn(ViewId, DialogRequest) -> Message>>,
+    on_data_cleared: Option<Message>,
+    on_new_window_request: Option<NewWindowCallback<Message>>,
+    zoom_shortcuts: bool,
+    on_zoom_change: Option<Box<dyn Fn(ViewId, f32) -> Message>>,
+    on_capture: Option<Box<dyn Fn(ViewId, ImageInfo) -> Message>>,
+    on_find_result: Option<Box<dyn Fn(ViewId, FindResult) -> Message>>,
+    on_error: Option<Box<dyn Fn(Error) -> Message>>,
+}
+
+impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView<Engine, Message> {
+    /// Create new Advanced Webview widget
+    pub fn new() -> Self {
+        Self::with_engine(Engine::default())
+    }
 }
 
 impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> Default
     for WebView<Engine, Message>
 {
     fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Engine: engines::Engine, Message: Send + Clone + 'static> WebView<Engine, Message> {
+    // The size new views are created at, until their widget is laid out
+    const DEFAULT_VIEW_SIZE: Size<u32> = Size::new(1920, 1080);
+
+    /// Create new Advanced Webview widget around an engine that was already set up,
+    /// like one configured with its own builder
+    pub fn with_engine(engine: Engine) -> Self {
         WebView {
-            engine: Engine::default(),
-            view_size: Size::new(1920, 1080),
+            engine,
+            view_sizes: Vec::new(),
+            scale_factor: 1.0,
             on_close_view: None,
             on_create_view: None,
             on_url_change: None,
             urls: Vec::new(),
             on_title_change: None,
             titles: Vec::new(),
+            on_load_start: None,
+            on_dom_ready: None,
+            on_load_finish: None,
+            on_load_fail: None,
+            on_history_change: None,
+            on_script_result: None,
+            on_message: None,
+            on_console_message: None,
+            #[cfg(feature = "log")]
+            log_console_messages: false,
+            scheme_handler: None,
+            on_navigation_request: None,
+            on_dialog_request: None,
+            on_data_cleared: None,
+            on_new_window_request: None,
+            zoom_shortcuts: false,
+            on_zoom_change: None,
+            on_capture: None,
+            on_find_result: None,
+            on_error: None,
         }
     }
-}
 
-impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView<Engine, Message> {
-    /// Create new Advanced Webview widget
-    pub fn new() -> Self {
-        Self::default()
+    /// Gives access to the underlying engine, for example to inspect it in tests
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Gives mutable access to the underlying engine,
+    /// for example to simulate what a page does in tests
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Serves files bundled into the app under `file:///app/`, see [`Assets`]
+    pub fn assets(mut self, assets: Assets) -> Self {
+        self.engine.set_assets(assets);
+        self
     }
 
     /// Subscribe to create view events
@@ -102,42 +237,405 @@ impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView
         self
     }
 
+    /// Subscribe to views starting to load a page
+    pub fn on_load_start(
+        mut self,
+        on_load_start: impl Fn(ViewId, String) -> Message + 'static,
+    ) -> Self {
+        self.on_load_start = Some(Box::new(on_load_start));
+        self
+    }
+
+    /// Subscribe to pages being parsed, so scripts can use the DOM
+    pub fn on_dom_ready(
+        mut self,
+        on_dom_ready: impl Fn(ViewId, String) -> Message + 'static,
+    ) -> Self {
+        self.on_dom_ready = Some(Box::new(on_dom_ready));
+        self
+    }
+
+    /// Subscribe to views finishing loading a page
+    pub fn on_load_finish(
+        mut self,
+        on_load_finish: impl Fn(ViewId, String) -> Message + 'static,
+    ) -> Self {
+        self.on_load_finish = Some(Box::new(on_load_finish));
+        self
+    }
+
+    /// Subscribe to views failing to load a page
+    pub fn on_load_fail(
+        mut self,
+        on_load_fail: impl Fn(ViewId, LoadFailure) -> Message + 'static,
+    ) -> Self {
+        self.on_load_fail = Some(Box::new(on_load_fail));
+        self
+    }
+
+    /// Subscribe to changes in a view's back/forward history
+    pub fn on_history_change(
+        mut self,
+        on_history_change: impl Fn(ViewId) -> Message + 'static,
+    ) -> Self {
+        self.on_history_change = Some(Box::new(on_history_change));
+        self
+    }
+
+    /// Subscribe to the outcome of `Action::EvaluateJavaScript`,
+    /// either the script's result as a string or the message of the exception it threw
+    pub fn on_script_result(
+        mut self,
+        on_script_result: impl Fn(ViewId, ScriptResult) -> Message + 'static,
+    ) -> Self {
+        self.on_script_result = Some(Box::new(on_script_result));
+        self
+    }
+
+    /// Subscribe to messages pages send with `window.iced.postMessage(message)`
+    ///
+    /// Messages that are not strings are sent as JSON
+    pub fn on_message(mut self, on_message: impl Fn(ViewId, String) -> Message + 'static) -> Self {
+        self.on_message = Some(Box::new(on_message));
+        self
+    }
+
+    /// Subscribe to messages pages log to their console,
+    /// including exceptions that nothing caught
+    pub fn on_console_message(
+        mut self,
+        on_console_message: impl Fn(ViewId, ConsoleMessage) -> Message + 'static,
+    ) -> Self {
+        self.on_console_message = Some(Box::new(on_console_message));
+        self
+    }
+
+    /// Forwards every view's console messages to the `log` crate,
+    /// so they show up next to the app's own logs
+    #[cfg(feature = "log")]
+    pub fn log_console_messages(mut self) -> Self {
+        self.log_console_messages = true;
+        self
+    }
+
+    /// Answers the requests pages make with `fetch` to `scheme://` urls, like `app://api/users`,
+    /// so they can use the app's data without a local HTTP server.
+    /// Only the app's own pages, bundled as assets or loaded from html, can reach it, never websites
+    ///
+    /// The handler runs as a task, and its response is routed back into the webview through
+    /// `to_message`, which wraps the webview's actions into the app's messages
+    pub fn scheme_handler(
+        mut self,
+        scheme: &str,
+        handler: impl Fn(Request) -> Task<Response> + 'static,
+        to_message: impl Fn(Action) -> Message + Send + Sync + 'static,
+    ) -> Self {
+        self.engine.set_scheme(scheme);
+        self.scheme_handler = Some(SchemeHandler::new(handler, to_message));
+        self
+    }
+
+    /// Decides whether a view may navigate, when the user clicks a link,
+    /// the page redirects or `Action::GoToUrl` is sent
+    ///
+    /// It is asked for navigations in any view and navigations are held until it decides
+    pub fn on_navigation_request(
+        mut self,
+        on_navigation_request: impl Fn(ViewId, Url, NavigationKind) -> NavigationDecision<Message>
+            + 'static,
+    ) -> Self {
+        self.engine.intercept_navigation(true);
+        self.on_navigation_request = Some(Box::new(on_navigation_request));
+        self
+    }
+
+    /// Subscribe to pages asking to open a url in a new window,
+    /// like with a `target="_blank"` link or `window.open`
+    ///
+    /// Nothing is opened unless the app creates the child view itself with `Action::CreateView`,
+    /// whose id it then gets through `on_create_view`
+    pub fn on_new_window_request(
+        mut self,
+        on_new_window_request: impl Fn(ViewId, Url, Opener) -> Message + 'static,
+    ) -> Self {
+        self.on_new_window_request = Some(Box::new(on_new_window_request));
+        self
+    }
+
+    /// Subscribe to dialogs opened by pages, with `alert` or with `window.iced`'s
+    /// `alert`, `confirm` and `prompt`, which are answered with `Action::AnswerDialog`
+    ///
+    /// The promises `window.iced.confirm` and `window.iced.prompt` return resolve to the answer.
+    /// Scripts can not wait for the app, so a page's own blocking `confirm` and `prompt` are not reported.
+    /// Without a subscriber, dialogs are dismissed straight away
+    pub fn on_dialog_request(
+        mut self,
+        on_dialog_request: impl Fn(ViewId, DialogRequest) -> Message + 'static,
+    ) -> Self {
+        self.on_dialog_request = Some(Box::new(on_dialog_request));
+        self
+    }
+
+    /// Subscribe to `Action::ClearData` finishing
+    ///
+    /// It is only sent once the engine has removed the data, which may be after later actions ran.
+    /// Engines that can not clear it send `on_error` instead, without removing any of it
+    pub fn on_data_cleared(mut self, on_data_cleared: Message) -> Self {
+        self.on_data_cleared = Some(on_data_cleared);
+        self
+    }
+
+    /// Zooms a view when Ctrl (Cmd on macOS) is held with the mouse wheel, +, - or 0,
+    /// like in browsers, instead of passing them on to its page
+    pub fn zoom_shortcuts(mut self) -> Self {
+        self.zoom_shortcuts = true;
+        self
+    }
+
+    /// Subscribe to views' zoom changing
+    pub fn on_zoom_change(
+        mut self,
+        on_zoom_change: impl Fn(ViewId, f32) -> Message + 'static,
+    ) -> Self {
+        self.on_zoom_change = Some(Box::new(on_zoom_change));
+        self
+    }
+
+    /// Subscribe to views captured with `Action::CaptureView` or `Action::CaptureFullPage`
+    ///
+    /// The image can be saved with [`ImageInfo::to_png`] or shown with [`ImageInfo::to_handle`]
+    pub fn on_capture(
+        mut self,
+        on_capture: impl Fn(ViewId, ImageInfo) -> Message + 'static,
+    ) -> Self {
+        self.on_capture = Some(Box::new(on_capture));
+        self
+    }
+
+    /// Subscribe to the outcome of `Action::Find`, with the selected match and how many matches there are
+    pub fn on_find_result(
+        mut self,
+        on_find_result: impl Fn(ViewId, FindResult) -> Message + 'static,
+    ) -> Self {
+        self.on_find_result = Some(Box::new(on_find_result));
+        self
+    }
+
+    /// Subscribe to errors, such as actions sent to views that have already been closed
+    pub fn on_error(mut self, on_error: impl Fn(Error) -> Message + 'static) -> Self {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
     /// Passes update to webview
     pub fn update(&mut self, action: Action) -> Task<Message> {
         let mut tasks = Vec::new();
+        let mut errors = Vec::new();
 
         // Check url & title for changes and callback if so
         for (id, url) in self.urls.iter_mut() {
             if let Some(on_url_change) = &self.on_url_change {
-                let engine_url = self.engine.get_url(*id);
-                if *url != engine_url {
-                    *url = engine_url.clone();
-                    tasks.push(Task::done(on_url_change(*id, engine_url)));
+                match self.engine.get_url(*id) {
+                    Ok(engine_url) => {
+                        if *url != engine_url {
+                            *url = engine_url.clone();
+                            tasks.push(Task::done(on_url_change(*id, engine_url)));
+                        }
+                    }
+                    Err(error) => errors.push(error),
                 }
             }
         }
         for (id, title) in self.titles.iter_mut() {
             if let Some(on_title_change) = &self.on_title_change {
-                let engine_title = self.engine.get_title(*id);
-                if *title != engine_title {
-                    *title = engine_title.clone();
-                    tasks.push(Task::done(on_title_change(*id, engine_title)));
+                match self.engine.get_title(*id) {
+                    Ok(engine_title) => {
+                        if *title != engine_title {
+                            *title = engine_title.clone();
+                            tasks.push(Task::done(on_title_change(*id, engine_title)));
+                        }
+                    }
+                    Err(error) => errors.push(error),
+                }
+            }
+        }
+
+        if let Err(error) = self.handle_action(action, &mut tasks) {
+            errors.push(error);
+        }
+
+        for (id, event) in self.engine.take_events() {
+            #[cfg(feature = "log")]
+            if let ViewEvent::ConsoleMessage(message) = &event {
+                if self.log_console_messages {
+                    message.log(id);
+                }
+            }
+            match event {
+                ViewEvent::Request(request_id, request) => {
+                    match self.request_task(id, request_id, request) {
+                        Ok(task) => tasks.push(task),
+                        Err(error) => errors.push(error),
+                    }
+                }
+                ViewEvent::NavigationRequested(url, kind) => {
+                    if let Err(error) = self.navigate(id, url, kind, &mut tasks) {
+                        errors.push(error);
+                    }
                 }
+                ViewEvent::DialogRequested(dialog_id, dialog) => {
+                    match self.dialog_message(id, dialog_id, dialog) {
+                        Ok(message) => tasks.extend(message.map(Task::done)),
+                        Err(error) => errors.push(error),
+                    }
+                }
+                event => tasks.extend(self.view_event_message(id, event).map(Task::done)),
+            }
+        }
+
+        for cleared in self.engine.take_cleared_data() {
+            match cleared {
+                Ok(()) => tasks.extend(self.on_data_cleared.clone().map(Task::done)),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if let Some(on_error) = &self.on_error {
+            tasks.extend(errors.into_iter().map(|error| Task::done(on_error(error))));
+        }
+
+        Task::batch(tasks)
+    }
+
+    /// Asks `on_navigation_request` whether a view may navigate, and carries out its decision
+    fn navigate(
+        &mut self,
+        id: ViewId,
+        url: String,
+        kind: NavigationKind,
+        tasks: &mut Vec<Task<Message>>,
+    ) -> Result<(), Error> {
+        let decision = match (&self.on_navigation_request, Url::parse(&url)) {
+            (Some(on), Ok(url)) => on(id, url, kind),
+            _ => NavigationDecision::Allow,
+        };
+        match decision {
+            NavigationDecision::Allow => self.engine.goto(id, PageType::Url(url)),
+            NavigationDecision::Cancel => Ok(()),
+            // in the same session as the view it came from
+            NavigationDecision::OpenInNewView => {
+                let session = self.engine.get_session(id)?;
+                self.handle_action(Action::CreateView(PageType::Url(url), session), tasks)
+            }
+            NavigationDecision::HandOff(message) => {
+                tasks.push(Task::done(message));
+                Ok(())
             }
         }
+    }
+
+    /// Hands a dialog to `on_dialog_request`, or dismisses it if there is none
+    fn dialog_message(
+        &mut self,
+        id: ViewId,
+        dialog_id: DialogId,
+        dialog: Dialog,
+    ) -> Result<Option<Message>, Error> {
+        match &self.on_dialog_request {
+            Some(on) => {
+                let request = DialogRequest::new(id, dialog_id, dialog);
+                Ok(Some(on(id, request)))
+            }
+            None => {
+                self.engine
+                    .answer_dialog(id, dialog_id, DialogAnswer::Dismiss)?;
+                Ok(None)
+            }
+        }
+    }
 
+    /// Runs the scheme handler for a request, or turns the request away if there is none
+    fn request_task(
+        &mut self,
+        id: ViewId,
+        request_id: RequestId,
+        request: Request,
+    ) -> Result<Task<Message>, Error> {
+        match &self.scheme_handler {
+            Some(handler) => Ok(handler.handle(request, move |response| {
+                Action::Respond(id, request_id, response)
+            })),
+            None => {
+                self.engine.respond(id, request_id, Response::not_found())?;
+                Ok(Task::none())
+            }
+        }
+    }
+
+    /// Zooms a view, reporting the new zoom to `on_zoom_change` if it changed
+    fn zoom(&mut self, id: ViewId, zoom: f32, tasks: &mut Vec<Task<Message>>) -> Result<(), Error> {
+        let zoom = clamp_zoom(zoom);
+        if self.engine.get_zoom(id)? != zoom {
+            self.engine.set_zoom(id, zoom)?;
+            self.engine.request_render(id)?;
+            if let Some(on_zoom_change) = &self.on_zoom_change {
+                tasks.push(Task::done(on_zoom_change(id, zoom)));
+            }
+        }
+        Ok(())
+    }
+
+    fn view_event_message(&self, id: ViewId, event: ViewEvent) -> Option<Message> {
+        match event {
+            ViewEvent::LoadStarted(url) => self.on_load_start.as_ref().map(|on| on(id, url)),
+            ViewEvent::DomReady(url) => self.on_dom_ready.as_ref().map(|on| on(id, url)),
+            ViewEvent::LoadFinished(url) => self.on_load_finish.as_ref().map(|on| on(id, url)),
+            ViewEvent::LoadFailed(failure) => self.on_load_fail.as_ref().map(|on| on(id, failure)),
+            ViewEvent::HistoryChanged => self.on_history_change.as_ref().map(|on| on(id)),
+            ViewEvent::ScriptEvaluated(result) => {
+                self.on_script_result.as_ref().map(|on| on(id, result))
+            }
+            ViewEvent::Message(message) => self.on_message.as_ref().map(|on| on(id, message)),
+            ViewEvent::ConsoleMessage(message) => {
+                self.on_console_message.as_ref().map(|on| on(id, message))
+            }
+            ViewEvent::FindResult(result) => self.on_find_result.as_ref().map(|on| on(id, result)),
+            ViewEvent::NewWindowRequested(url, opener) => {
+                let url = Url::parse(&url).ok()?;
+                self.on_new_window_request
+                    .as_ref()
+                    .map(|on| on(id, url, opener))
+            }
+            ViewEvent::FullPageCaptured(image) => self.on_capture.as_ref().map(|on| on(id, image)),
+            // handled in update instead
+            ViewEvent::Request(..)
+            | ViewEvent::DialogRequested(..)
+            | ViewEvent::NavigationRequested(..) => None,
+        }
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        tasks: &mut Vec<Task<Message>>,
+    ) -> Result<(), Error> {
         match action {
             Action::CloseView(id) => {
-                self.engine.remove_view(id);
                 self.urls.retain(|url| url.0 != id);
                 self.titles.retain(|title| title.0 != id);
+                self.view_sizes.retain(|size| size.0 != id);
+                self.engine.remove_view(id)?;
 
                 if let Some(on_view_close) = &self.on_close_view {
                     tasks.push(Task::done((on_view_close)(id)))
                 }
             }
-            Action::CreateView(page_type) => {
-                let id = self.engine.new_view(self.view_size, Some(page_type));
+            Action::CreateView(page_type, session) => {
+                let id = self
+                    .engine
+                    .new_view(Self::DEFAULT_VIEW_SIZE, Some(page_type), session)?;
+                self.view_sizes.push((id, Self::DEFAULT_VIEW_SIZE));
+                self.engine.set_scale_factor(id, self.scale_factor)?;
                 self.urls.push((id, String::new()));
                 self.titles.push((id, String::new()));
 
@@ -145,73 +643,216 @@ impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView
                     tasks.push(Task::done((on_view_create)(id)))
                 }
             }
+            Action::Focus(id) => {
+                self.engine.focus(id)?;
+                self.engine.request_render(id)?;
+            }
+            Action::Unfocus(id) => {
+                self.engine.unfocus(id)?;
+                self.engine.request_render(id)?;
+            }
             Action::GoBackward(id) => {
-                self.engine.go_back(id);
-                self.engine.request_render(id, self.view_size);
+                self.engine.go_back(id)?;
+                self.engine.request_render(id)?;
             }
             Action::GoForward(id) => {
-                self.engine.go_forward(id);
-                self.engine.request_render(id, self.view_size);
+                self.engine.go_forward(id)?;
+                self.engine.request_render(id)?;
             }
             Action::GoToUrl(id, url) => {
-                self.engine.goto(id, PageType::Url(url.to_string()));
-                self.engine.request_render(id, self.view_size);
+                self.navigate(id, url.to_string(), NavigationKind::GoTo, tasks)?;
+                self.engine.request_render(id)?;
             }
             Action::Refresh(id) => {
-                self.engine.refresh(id);
-                self.engine.request_render(id, self.view_size);
+                self.engine.refresh(id)?;
+                self.engine.request_render(id)?;
             }
             Action::SendKeyboardEvent(id, event) => {
-                self.engine.handle_keyboard_event(id, event);
-                self.engine.request_render(id, self.view_size);
+                self.engine.handle_keyboard_event(id, event)?;
+                self.engine.request_render(id)?;
             }
             Action::SendMouseEvent(id, point, event) => {
-                self.engine.handle_mouse_event(id, event, point);
-                self.engine.request_render(id, self.view_size);
+                self.engine.handle_mouse_event(id, event, point)?;
+                self.engine.request_render(id)?;
+            }
+            Action::EvaluateJavaScript(id, script) => {
+                self.engine.evaluate_script(id, &script)?;
+                self.engine.request_render(id)?;
+            }
+            Action::PostMessage(id, message) => {
+                self.engine.post_message(id, &message)?;
+                self.engine.request_render(id)?;
+            }
+            Action::Respond(id, request, response) => {
+                self.engine.respond(id, request, response)?;
+                self.engine.request_render(id)?;
+            }
+            Action::AnswerDialog(responder, answer) => {
+                self.engine
+                    .answer_dialog(responder.view, responder.dialog, answer)?;
+                self.engine.request_render(responder.view)?;
+            }
+            Action::ClearData(session, data) => {
+                self.engine.clear_data(session, data);
             }
             Action::Update(id) => {
                 self.engine.update();
-                self.engine.request_render(id, self.view_size);
+                self.engine.request_render(id)?;
             }
             Action::UpdateAll => {
                 self.engine.update();
-                self.engine.render(self.view_size);
+                self.engine.render();
+            }
+            Action::Resize(id, size) => {
+                self.engine.resize(id, size)?;
+                match self
+                    .view_sizes
+                    .iter_mut()
+                    .find(|view_size| view_size.0 == id)
+                {
+                    Some(view_size) => view_size.1 = size,
+                    None => self.view_sizes.push((id, size)),
+                }
+                self.engine.request_render(id)?;
+            }
+            Action::SetScaleFactor(scale_factor) => {
+                self.scale_factor = scale_factor;
+                for (id, _) in self.view_sizes.iter() {
+                    self.engine.set_scale_factor(*id, scale_factor)?;
+                    self.engine.request_render(*id)?;
+                }
+            }
+            Action::SetZoom(id, zoom) => self.zoom(id, zoom, tasks)?,
+            Action::ZoomIn(id) => self.zoom(id, zoom_in(self.engine.get_zoom(id)?), tasks)?,
+            Action::ZoomOut(id) => self.zoom(id, zoom_out(self.engine.get_zoom(id)?), tasks)?,
+            Action::ResetZoom(id) => self.zoom(id, 1.0, tasks)?,
+            Action::CaptureView(id) => {
+                self.engine.request_render(id)?;
+                if let Some(on_capture) = &self.on_capture {
+                    let image = self.engine.get_view(id)?.clone();
+                    tasks.push(Task::done(on_capture(id, image)));
+                }
             }
-            Action::Resize(size) => {
-                self.view_size = size;
-                self.engine.resize(size);
+            Action::Find {
+                view,
+                query,
+                case_sensitive,
+                forward,
+            } => {
+                self.engine.find(view, &query, case_sensitive, forward)?;
+                self.engine.request_render(view)?;
+            }
+            Action::StopFinding(id) => {
+                self.engine.stop_finding(id)?;
+                self.engine.request_render(id)?;
+            }
+            Action::CaptureFullPage(id) => {
+                self.engine.capture_full_page(id)?;
+                self.engine.request_render(id)?;
             }
         };
 
-        Task::batch(tasks)
+        Ok(())
+    }
+
+    /// Keeps the engine updated,
+    /// ticking fast only while a view is loading, animating or needs paint
+    ///
+    /// When every view is idle it only checks in occasionally, so timers in pages still fire
+    pub fn subscription(&self) -> Subscription<Action> {
+        let tick = if self.engine.needs_update() {
+            BUSY_TICK
+        } else {
+            IDLE_TICK
+        };
+        Subscription::batch([
+            time::every(tick).map(|_| Action::UpdateAll),
+            self.engine.subscription().map(|_| Action::UpdateAll),
+        ])
     }
 
     /// Like a normal `view()` method in iced, but takes an id of the desired view
-    pub fn view<T>(&self, id: usize) -> Element<Action, T> {
-        WebViewWidget::new(
-            id,
-            self.view_size,
-            self.engine.get_view(id),
-            self.engine.get_cursor(id),
-        )
+    ///
+    /// If the view does not exist, a blank view is shown
+    ///
+    /// Each widget resizes its view to fit itself whenever the widget's own size changes.
+    /// A view shown in several places at once is sized by the widget that was resized last,
+    /// and the others stretch its frame to fit
+    pub fn view<T>(&self, id: usize) -> Element<'_, Action, T> {
+        let size = self.view_size(id);
+        match (self.engine.get_view(id), self.engine.get_cursor(id)) {
+            (Ok(image_info), Ok(cursor)) => {
+                WebViewWidget::new(id, size, self.scale_factor, image_info, cursor)
+            }
+            _ => WebViewWidget::new(
+                id,
+                size,
+                self.scale_factor,
+                &ImageInfo::blank(size.width, size.height),
+                Interaction::Idle,
+            ),
+        }
+        .zoom_shortcuts(self.zoom_shortcuts)
         .into()
     }
+
+    fn view_size(&self, id: ViewId) -> Size<u32> {
+        self.view_sizes
+            .iter()
+            .find(|view_size| view_size.0 == id)
+            .map_or(Self::DEFAULT_VIEW_SIZE, |view_size| view_size.1)
+    }
+}
+
+/// Tracks whether the widget was clicked into, so only it receives keyboard input
+#[derive(Default)]
+struct State {
+    is_focused: bool,
+    // held while scrolling, to tell zooming with the wheel apart
+    modifiers: keyboard::Modifiers,
+    // the view this widget last resized and the size it asked for, so widgets showing
+    // the same view at different sizes do not keep resizing it back and forth
+    resized: Option<(ViewId, Size<u32>)>,
 }
 
 struct WebViewWidget {
     id: ViewId,
+    // in physical pixels
     bounds: Size<u32>,
+    scale_factor: f32,
     image: Image<Handle>,
     cursor: Interaction,
+    zoom_shortcuts: bool,
 }
 
 impl WebViewWidget {
-    fn new(id: ViewId, bounds: Size<u32>, image: &ImageInfo, cursor: Interaction) -> Self {
+    fn new(
+        id: ViewId,
+        bounds: Size<u32>,
+        scale_factor: f32,
+        image: &ImageInfo,
+        cursor: Interaction,
+    ) -> Self {
         Self {
             id,
             bounds,
+            scale_factor,
             image: image.as_image(),
             cursor,
+            zoom_shortcuts: false,
+        }
+    }
+
+    fn zoom_shortcuts(mut self, zoom_shortcuts: bool) -> Self {
+        self.zoom_shortcuts = zoom_shortcuts;
+        self
+    }
+
+    fn zoom_action(&self, shortcut: ZoomShortcut) -> Action {
+        match shortcut {
+            ZoomShortcut::In => Action::ZoomIn(self.id),
+            ZoomShortcut::Out => Action::ZoomOut(self.id),
+            ZoomShortcut::Reset => Action::ResetZoom(self.id),
         }
     }
 }
@@ -220,6 +861,14 @@ impl<Renderer, Theme> Widget<Action, Theme, Renderer> for WebViewWidget
 where
     Renderer: iced::advanced::image::Renderer<Handle = iced::advanced::image::Handle>,
 {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
     fn size(&self) -> Size<Length> {
         Size {
             width: Length::Fill,
@@ -260,7 +909,7 @@ where
 
     fn on_event(
         &mut self,
-        _state: &mut Tree,
+        tree: &mut Tree,
         event: Event,
         layout: Layout<'_>,
         cursor: mouse::Cursor,
@@ -269,17 +918,49 @@ where
         shell: &mut Shell<'_, Action>,
         _viewport: &Rectangle,
     ) -> event::Status {
-        let size = Size::new(layout.bounds().width as u32, layout.bounds().height as u32);
-        if self.bounds != size {
-            shell.publish(Action::Resize(size));
+        let state = tree.state.downcast_mut::<State>();
+        let size = physical_size(layout.bounds(), self.scale_factor);
+        if self.bounds != size && state.resized != Some((self.id, size)) {
+            state.resized = Some((self.id, size));
+            shell.publish(Action::Resize(self.id, size));
         }
 
+        if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+            state.modifiers = modifiers;
+        }
         match event {
-            Event::Keyboard(event) => {
-                shell.publish(Action::SendKeyboardEvent(self.id, event));
+            Event::Keyboard(event) if state.is_focused => {
+                let shortcut = ZoomShortcut::from_key(&event).filter(|_| self.zoom_shortcuts);
+                shell.publish(match shortcut {
+                    Some(shortcut) => self.zoom_action(shortcut),
+                    None => Action::SendKeyboardEvent(self.id, event),
+                });
+                return Status::Captured;
             }
             Event::Mouse(event) => {
+                // clicking the view focuses it, clicking anywhere else unfocuses it
+                if let mouse::Event::ButtonPressed(_) = event {
+                    let is_over = cursor.is_over(layout.bounds());
+                    if state.is_focused != is_over {
+                        state.is_focused = is_over;
+                        shell.publish(if is_over {
+                            Action::Focus(self.id)
+                        } else {
+                            Action::Unfocus(self.id)
+                        });
+                    }
+                }
                 if let Some(point) = cursor.position_in(layout.bounds()) {
+                    // zooming with the wheel takes the place of scrolling the page
+                    if let mouse::Event::WheelScrolled { delta } = event {
+                        let shortcut = ZoomShortcut::from_wheel(delta, state.modifiers)
+                            .filter(|_| self.zoom_shortcuts);
+                        if let Some(shortcut) = shortcut {
+                            shell.publish(self.zoom_action(shortcut));
+                            return Status::Captured;
+                        }
+                    }
+                    let (event, point) = to_physical(event, point, self.scale_factor);
                     shell.publish(Action::SendMouseEvent(self.id, event, point));
                 }
             }
@@ -313,3 +994,214 @@ where
         Self::new(widget)
     }
 }
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::engines::mock::MockEvent;
+    use crate::engines::Engine as _;
+    use crate::webview::messages;
+    use crate::Mock;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Message {
+        Created(ViewId),
+        Closed(ViewId),
+        Zoom(ViewId, f32),
+        Captured(ViewId, Size<u32>),
+        Found(ViewId, FindResult),
+        DataCleared,
+        Error(Error),
+    }
+
+    /// A webview reporting everything, with a number of views
+    fn webview(views: usize) -> (WebView<Mock, Message>, Vec<ViewId>) {
+        let mut webview = WebView::<Mock, Message>::new()
+            .on_create_view(Message::Created)
+            .on_close_view(Message::Closed)
+            .on_zoom_change(Message::Zoom)
+            .on_capture(|id, image| Message::Captured(id, Size::new(image.width(), image.height())))
+            .on_find_result(Message::Found)
+            .on_data_cleared(Message::DataCleared)
+            .on_error(Message::Error);
+        for view in 0..views {
+            let page = PageType::Url(format!("https://example.com/{view}"));
+            let _ = webview.update(Action::CreateView(page, Session::Default));
+        }
+        let ids = webview.engine().view_ids();
+        (webview, ids)
+    }
+
+    #[test]
+    fn creating_a_view_reports_its_id() {
+        let (mut webview, _) = webview(0);
+        let page = PageType::Url("https://example.com".to_string());
+
+        let created = messages(webview.update(Action::CreateView(page, Session::Default)));
+        assert_eq!(created, [Message::Created(webview.engine().view_ids()[0])]);
+    }
+
+    #[test]
+    fn closing_a_view_forgets_it() {
+        let (mut webview, ids) = webview(2);
+
+        assert_eq!(
+            messages(webview.update(Action::CloseView(ids[0]))),
+            [Message::Closed(ids[0])]
+        );
+        assert_eq!(webview.engine().view_ids(), [ids[1]]);
+        assert_eq!(messages(webview.update(Action::UpdateAll)), []);
+        assert_eq!(
+            messages(webview.update(Action::Focus(ids[0]))),
+            [Message::Error(Error::UnknownView(ids[0]))]
+        );
+    }
+
+    #[test]
+    fn update_all_paints_views() {
+        let (mut webview, ids) = webview(1);
+        let frames = webview.engine().frames(ids[0]).unwrap();
+
+        let _ = webview.update(Action::UpdateAll);
+        assert_eq!(webview.engine().frames(ids[0]).unwrap(), frames + 1);
+    }
+
+    #[test]
+    fn views_zoom_separately() {
+        let (mut webview, ids) = webview(2);
+
+        assert_eq!(
+            messages(webview.update(Action::SetZoom(ids[0], 2.0))),
+            [Message::Zoom(ids[0], 2.0)]
+        );
+        assert_eq!(
+            messages(webview.update(Action::ZoomOut(ids[1]))),
+            [Message::Zoom(ids[1], zoom_out(1.0))]
+        );
+        assert_eq!(
+            messages(webview.update(Action::ResetZoom(ids[0]))),
+            [Message::Zoom(ids[0], 1.0)]
+        );
+        assert_eq!(webview.engine().get_zoom(ids[1]), Ok(zoom_out(1.0)));
+    }
+
+    #[test]
+    fn find_reports_the_result_for_its_view() {
+        let (mut webview, ids) = webview(2);
+        webview.engine_mut().set_find_result(ids[1], 1, 3).unwrap();
+
+        let find = Action::Find {
+            view: ids[1],
+            query: "iced".to_string(),
+            case_sensitive: false,
+            forward: true,
+        };
+        let found = FindResult {
+            query: "iced".to_string(),
+            case_sensitive: false,
+            active_match: 1,
+            total_matches: 3,
+        };
+        assert_eq!(
+            messages(webview.update(find)),
+            [Message::Found(ids[1], found)]
+        );
+        let _ = webview.update(Action::StopFinding(ids[1]));
+        assert_eq!(
+            webview.engine().events(ids[1]).unwrap(),
+            [
+                MockEvent::Find {
+                    query: "iced".to_string(),
+                    case_sensitive: false,
+                    forward: true,
+                },
+                MockEvent::StopFinding,
+            ]
+        );
+        assert_eq!(webview.engine().events(ids[0]).unwrap(), []);
+    }
+
+    #[test]
+    fn capturing_sends_the_view() {
+        let (mut webview, ids) = webview(1);
+        let size = WebView::<Mock, Message>::DEFAULT_VIEW_SIZE;
+
+        assert_eq!(
+            messages(webview.update(Action::CaptureView(ids[0]))),
+            [Message::Captured(ids[0], size)]
+        );
+        assert_eq!(
+            messages(webview.update(Action::CaptureFullPage(ids[0]))),
+            [Message::Captured(ids[0], size)]
+        );
+    }
+
+    #[test]
+    fn widgets_sharing_a_view_stop_resizing_it() {
+        let (mut webview, ids) = webview(1);
+        let panes = [Size::new(400., 300.), Size::new(200., 100.)];
+        let widget = |webview: &WebView<Mock, Message>| {
+            let size = webview.view_size(ids[0]);
+            WebViewWidget::new(
+                ids[0],
+                size,
+                1.0,
+                &ImageInfo::blank(1, 1),
+                Interaction::Idle,
+            )
+        };
+        let mut trees: Vec<Tree> = panes
+            .iter()
+            .map(|_| Tree {
+                tag: tree::Tag::of::<State>(),
+                state: tree::State::new(State::default()),
+                children: Vec::new(),
+            })
+            .collect();
+
+        // a few view passes, with every widget getting an event in each
+        let mut resizes = Vec::new();
+        for _ in 0..3 {
+            for (pane, tree) in panes.iter().zip(trees.iter_mut()) {
+                let node = layout::Node::new(*pane);
+                let mut actions = Vec::new();
+                let _ = Widget::<Action, iced::Theme, ()>::on_event(
+                    &mut widget(&webview),
+                    tree,
+                    Event::Mouse(mouse::Event::CursorLeft),
+                    Layout::new(&node),
+                    mouse::Cursor::Unavailable,
+                    &(),
+                    &mut advanced::clipboard::Null,
+                    &mut Shell::new(&mut actions),
+                    &Rectangle::with_size(*pane),
+                );
+                for action in actions {
+                    resizes.push(action.clone());
+                    let _ = webview.update(action);
+                }
+            }
+        }
+        assert_eq!(
+            resizes,
+            [
+                Action::Resize(ids[0], Size::new(400, 300)),
+                Action::Resize(ids[0], Size::new(200, 100))
+            ]
+        );
+        assert_eq!(webview.view_size(ids[0]), Size::new(200, 100));
+    }
+
+    #[test]
+    fn clearing_data_reports_it() {
+        let session = Session::Private("work".to_string());
+        let (mut webview, _) = webview(1);
+
+        let clear = Action::ClearData(Some(session.clone()), BrowsingData::ALL);
+        assert_eq!(messages(webview.update(clear)), [Message::DataCleared]);
+        assert_eq!(
+            webview.engine().cleared_data(),
+            [(Some(session), BrowsingData::ALL)]
+        );
+    }
+}
@@ -3,18 +3,27 @@ use iced::advanced::{
     graphics::core::event,
     layout,
     renderer::{self},
-    widget::Tree,
+    widget::tree::{self, Tree},
     Clipboard, Layout, Shell, Widget,
 };
 use iced::event::Status;
 use iced::keyboard;
 use iced::mouse::{self, Interaction};
 use iced::widget::image::{Handle, Image};
-use iced::{Element, Point, Size, Task};
+use iced::{time, Element, Point, Size, Subscription, Task};
 use iced::{Event, Length, Rectangle};
+use std::time::Duration;
 use url::Url;
 
-use crate::{engines, ImageInfo, PageType, ViewId};
+use super::{
+    clamp_zoom, physical_size, to_physical, zoom_in, zoom_out, DialogRequest, DialogResponder,
+    NavigationDecision, SchemeHandler, ZoomShortcut,
+};
+use crate::{
+    engines, Assets, BrowsingData, ConsoleMessage, Dialog, DialogAnswer, DialogId, Error,
+    FindResult, ImageInfo, LoadFailure, NavigationKind, PageType, Request, RequestId, Response,
+    ScriptResult, Session, ViewEvent, ViewId,
+};
 
 #[allow(missing_docs)]
 #[derive(Debug, Clone, PartialEq)]
@@ -27,18 +36,77 @@ pub enum Action {
     /// Closes specific view index
     CloseView(u32),
     /// Creates a new view and makes its index view + 1
-    CreateView(PageType),
+    ///
+    /// Views in different sessions do not share cookies or storage,
+    /// so users can be logged in separately in each
+    CreateView(PageType, Session),
+    /// Focuses the current view so it receives keyboard input, sent by the widget when clicked
+    Focus,
+    /// Unfocuses the current view, sent by the widget when something else is clicked
+    Unfocus,
     GoBackward,
     GoForward,
     GoToUrl(Url),
     Refresh,
     SendKeyboardEvent(keyboard::Event),
     SendMouseEvent(mouse::Event, Point),
+    /// Runs JavaScript in the current view, its outcome is reported to `on_script_result`
+    EvaluateJavaScript(String),
+    /// Sends a message to the current view's page,
+    /// where it arrives as a `message` event on `window.iced`
+    PostMessage(String),
+    /// Answers a dialog reported to `on_dialog_request`, in whichever view opened it
+    AnswerDialog(DialogResponder, DialogAnswer),
+    /// Removes data views stored in a session, or in every session when none is given,
+    /// then sends `on_data_cleared`, or `on_error` if the engine could not clear it
+    ClearData(Option<Session>, BrowsingData),
+    /// Answers a page's request to the app's scheme, sent by the webview once its handler finishes
+    ///
+    /// Requests are answered in the view that made them, even if it is no longer the current one
+    Respond(ViewId, RequestId, Response),
     /// Allows users to control when the browser engine proccesses interactions in subscriptions
     Update,
+    /// Resizes the current view to a size in physical pixels, sent by the widget when its bounds change
     Resize(Size<u32>),
+    /// Draws every view at the window's scale factor, so pages stay sharp on HiDPI displays
+    ///
+    /// Get it with [`window_scale_factor`](crate::window_scale_factor),
+    /// multiplied by the app's own scale factor if it sets one
+    SetScaleFactor(f32),
+    /// Zooms the current view's page, where 1.0 shows it at its normal size
+    SetZoom(f32),
+    /// Zooms the current view in by one step, like Ctrl and + in browsers
+    ZoomIn,
+    /// Zooms the current view out by one step, like Ctrl and - in browsers
+    ZoomOut,
+    /// Shows the current view's page at its normal size again, like Ctrl and 0 in browsers
+    ResetZoom,
+    /// Renders a view by its index and sends a copy of it to `on_capture`,
+    /// at the size it was last shown in physical pixels
+    CaptureView(u32),
+    /// Renders a view's whole page by its index, including what is scrolled out of view,
+    /// and sends it to `on_capture` as one image once it is done
+    CaptureFullPage(u32),
+    /// Finds text in the current view's page and selects the next match, or the previous one
+    /// when not `forward`, reporting the result to `on_find_result`
+    ///
+    /// Finding the same query again moves on from the selected match
+    Find {
+        query: String,
+        case_sensitive: bool,
+        forward: bool,
+    },
+    /// Stops finding in the current view, clearing the selected match
+    StopFinding,
 }
 
+// How often the engine is updated while a view is busy, roughly once a frame
+const BUSY_TICK: Duration = Duration::from_millis(16);
+// How often the engine is updated while every view is idle
+const IDLE_TICK: Duration = Duration::from_secs(1);
+
+type NavigationHook<Message> = Box<dyn Fn(Url, NavigationKind) -> NavigationDecision<Message>>;
+
 /// The Basic WebView widget that creates and shows webview(s)
 pub struct WebView<Engine, Message>
 where
@@ -46,6 +114,7 @@ where
 {
     engine: Engine,
     view_size: Size<u32>,
+    scale_factor: f32,
     current_view_index: Option<usize>, // the index corresponding to the view_ids list of ViewIds
     view_ids: Vec<ViewId>, // allow users to index by simple id like 0 or 1 instead of a true id
     on_close_view: Option<Message>,
@@ -54,23 +123,57 @@ where
     url: String,
     on_title_change: Option<Box<dyn Fn(String) -> Message>>,
     title: String,
+    on_load_start: Option<Box<dyn Fn(String) -> Message>>,
+    on_dom_ready: Option<Box<dyn Fn(String) -> Message>>,
+    on_load_finish: Option<Box<dyn Fn(String) -> Message>>,
+    on_load_fail: Option<Box<dyn Fn(LoadFailure) -> Message>>,
+    on_history_change: Option<Message>,
+    on_script_result: Option<Box<dyn Fn(ScriptResult) -> Message>>,
+    on_message: Option<Box<dyn Fn(String) -> Message>>,
+    on_console_message: Option<Box<dyn Fn(ConsoleMessage) -> Message>>,
+    #[cfg(feature = "log")]
+    log_console_messages: bool,
+    scheme_handler: Option<SchemeHandler<Action, Message>>,
+    on_navigation_request: Option<NavigationHook<Message>>,
+    on_dialog_request: Option<Box<dyn Fn(DialogRequest) -> Message>>,
+    on_data_cleared: Option<Message>,
+    zoom_shortcuts: bool,
+    on_zoom_change: Option<Box<dyn Fn(f32) -> Message>>,
+    on_capture: Option<Box<dyn Fn(u32, ImageInfo) -> Message>>,
+    on_find_result: Option<Box<dyn Fn(FindResult) -> Message>>,
+    on_error: Option<Box<dyn Fn(Error) -> Message>>,
 }
 
-impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView<Engine, Message> {
-    fn get_current_view_id(&self) -> ViewId {
-        *self
-            .view_ids
-            .get(self.current_view_index.expect(
-                "The current view index is not currently set. Ensure you call the Action prior",
-            ))
-            .expect("Could find view index for current view. Maybe its already been closed?")
-    }
-
-    fn index_as_view_id(&self, index: u32) -> usize {
-        *self
-            .view_ids
+impl<Engine: engines::Engine, Message: Send + Clone + 'static> WebView<Engine, Message> {
+    fn get_current_view_id(&self) -> Result<ViewId, Error> {
+        let index = self.current_view_index.ok_or(Error::NoCurrentView)?;
+        self.index_as_view_id(index as u32)
+    }
+
+    fn index_as_view_id(&self, index: u32) -> Result<ViewId, Error> {
+        self.view_ids
             .get(index as usize)
-            .expect("Failed to find that index, maybe its already been closed?")
+            .copied()
+            .ok_or(Error::UnknownViewIndex(index))
+    }
+
+    /// Forgets a closed view, keeping the current index on the same view,
+    /// or on its neighbour if the current view was the one closed
+    fn remove_view_index(&mut self, index: usize) {
+        self.view_ids.remove(index);
+        self.current_view_index = match self.current_view_index {
+            _ if self.view_ids.is_empty() => None,
+            Some(current) if index < current => Some(current - 1),
+            Some(current) => Some(current.min(self.view_ids.len() - 1)),
+            None => None,
+        };
+    }
+}
+
+impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView<Engine, Message> {
+    /// Create new basic WebView widget
+    pub fn new() -> Self {
+        Self::with_engine(Engine::default())
     }
 }
 
@@ -78,12 +181,21 @@ impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> Default
     for WebView<Engine, Message>
 {
     fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Engine: engines::Engine, Message: Send + Clone + 'static> WebView<Engine, Message> {
+    /// Create new basic WebView widget around an engine that was already set up,
+    /// like one configured with its own builder
+    pub fn with_engine(engine: Engine) -> Self {
         WebView {
-            engine: Engine::default(),
+            engine,
             view_size: Size {
                 width: 1920,
                 height: 1080,
             },
+            scale_factor: 1.0,
             current_view_index: None,
             view_ids: Vec::new(),
             on_close_view: None,
@@ -92,14 +204,43 @@ impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> Default
             url: String::new(),
             on_title_change: None,
             title: String::new(),
+            on_load_start: None,
+            on_dom_ready: None,
+            on_load_finish: None,
+            on_load_fail: None,
+            on_history_change: None,
+            on_script_result: None,
+            on_message: None,
+            on_console_message: None,
+            #[cfg(feature = "log")]
+            log_console_messages: false,
+            scheme_handler: None,
+            on_navigation_request: None,
+            on_dialog_request: None,
+            on_data_cleared: None,
+            zoom_shortcuts: false,
+            on_zoom_change: None,
+            on_capture: None,
+            on_find_result: None,
+            on_error: None,
         }
     }
-}
 
-impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView<Engine, Message> {
-    /// Create new basic WebView widget
-    pub fn new() -> Self {
-        Self::default()
+    /// Gives access to the underlying engine, for example to inspect it in tests
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Gives mutable access to the underlying engine,
+    /// for example to simulate what a page does in tests
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Serves files bundled into the app under `file:///app/`, see [`Assets`]
+    pub fn assets(mut self, assets: Assets) -> Self {
+        self.engine.set_assets(assets);
+        self
     }
 
     /// subscribe to create view events
@@ -129,135 +270,620 @@ impl<Engine: engines::Engine + Default, Message: Send + Clone + 'static> WebView
         self
     }
 
+    /// subscribe to the current view starting to load a page
+    pub fn on_load_start(mut self, on_load_start: impl Fn(String) -> Message + 'static) -> Self {
+        self.on_load_start = Some(Box::new(on_load_start));
+        self
+    }
+
+    /// subscribe to the current view's page being parsed, so scripts can use the DOM
+    pub fn on_dom_ready(mut self, on_dom_ready: impl Fn(String) -> Message + 'static) -> Self {
+        self.on_dom_ready = Some(Box::new(on_dom_ready));
+        self
+    }
+
+    /// subscribe to the current view finishing loading a page
+    pub fn on_load_finish(mut self, on_load_finish: impl Fn(String) -> Message + 'static) -> Self {
+        self.on_load_finish = Some(Box::new(on_load_finish));
+        self
+    }
+
+    /// subscribe to the current view failing to load a page
+    pub fn on_load_fail(mut self, on_load_fail: impl Fn(LoadFailure) -> Message + 'static) -> Self {
+        self.on_load_fail = Some(Box::new(on_load_fail));
+        self
+    }
+
+    /// subscribe to changes in the current view's back/forward history
+    pub fn on_history_change(mut self, on_history_change: Message) -> Self {
+        self.on_history_change = Some(on_history_change);
+        self
+    }
+
+    /// subscribe to the outcome of `Action::EvaluateJavaScript`,
+    /// either the script's result as a string or the message of the exception it threw
+    pub fn on_script_result(
+        mut self,
+        on_script_result: impl Fn(ScriptResult) -> Message + 'static,
+    ) -> Self {
+        self.on_script_result = Some(Box::new(on_script_result));
+        self
+    }
+
+    /// subscribe to messages the current view's page sends with `window.iced.postMessage(message)`
+    ///
+    /// Messages that are not strings are sent as JSON
+    pub fn on_message(mut self, on_message: impl Fn(String) -> Message + 'static) -> Self {
+        self.on_message = Some(Box::new(on_message));
+        self
+    }
+
+    /// Subscribe to messages the current view's page logs to their console,
+    /// including exceptions that nothing caught
+    pub fn on_console_message(
+        mut self,
+        on_console_message: impl Fn(ConsoleMessage) -> Message + 'static,
+    ) -> Self {
+        self.on_console_message = Some(Box::new(on_console_message));
+        self
+    }
+
+    /// Forwards every view's console messages to the `log` crate,
+    /// so they show up next to the app's own logs
+    #[cfg(feature = "log")]
+    pub fn log_console_messages(mut self) -> Self {
+        self.log_console_messages = true;
+        self
+    }
+
+    /// Answers the requests pages in any view make with `fetch` to `scheme://` urls, like `app://api/users`,
+    /// so they can use the app's data without a local HTTP server.
+    /// Only the app's own pages, bundled as assets or loaded from html, can reach it, never websites
+    ///
+    /// The handler runs as a task, and its response is routed back into the webview through
+    /// `to_message`, which wraps the webview's actions into the app's messages
+    pub fn scheme_handler(
+        mut self,
+        scheme: &str,
+        handler: impl Fn(Request) -> Task<Response> + 'static,
+        to_message: impl Fn(Action) -> Message + Send + Sync + 'static,
+    ) -> Self {
+        self.engine.set_scheme(scheme);
+        self.scheme_handler = Some(SchemeHandler::new(handler, to_message));
+        self
+    }
+
+    /// Decides whether a view may navigate, when the user clicks a link,
+    /// the page redirects or `Action::GoToUrl` is sent
+    ///
+    /// It is asked for navigations in any view, not just the current one, and navigations are held until it decides
+    pub fn on_navigation_request(
+        mut self,
+        on_navigation_request: impl Fn(Url, NavigationKind) -> NavigationDecision<Message> + 'static,
+    ) -> Self {
+        self.engine.intercept_navigation(true);
+        self.on_navigation_request = Some(Box::new(on_navigation_request));
+        self
+    }
+
+    /// Subscribe to dialogs opened by pages in any view, with `alert` or with `window.iced`'s
+    /// `alert`, `confirm` and `prompt`, which are answered with `Action::AnswerDialog`
+    ///
+    /// The promises `window.iced.confirm` and `window.iced.prompt` return resolve to the answer.
+    /// Scripts can not wait for the app, so a page's own blocking `confirm` and `prompt` are not reported.
+    /// Without a subscriber, dialogs are dismissed straight away
+    pub fn on_dialog_request(
+        mut self,
+        on_dialog_request: impl Fn(DialogRequest) -> Message + 'static,
+    ) -> Self {
+        self.on_dialog_request = Some(Box::new(on_dialog_request));
+        self
+    }
+
+    /// Subscribe to `Action::ClearData` finishing
+    ///
+    /// It is only sent once the engine has removed the data, which may be after later actions ran.
+    /// Engines that can not clear it send `on_error` instead, without removing any of it
+    pub fn on_data_cleared(mut self, on_data_cleared: Message) -> Self {
+        self.on_data_cleared = Some(on_data_cleared);
+        self
+    }
+
+    /// Zooms the current view when Ctrl (Cmd on macOS) is held with the mouse wheel, +, - or 0,
+    /// like in browsers, instead of passing them on to the page
+    pub fn zoom_shortcuts(mut self) -> Self {
+        self.zoom_shortcuts = true;
+        self
+    }
+
+    /// subscribe to the current view's zoom changing, including when changing to a view zoomed differently
+    pub fn on_zoom_change(mut self, on_zoom_change: impl Fn(f32) -> Message + 'static) -> Self {
+        self.on_zoom_change = Some(Box::new(on_zoom_change));
+        self
+    }
+
+    /// subscribe to views captured with `Action::CaptureView` or `Action::CaptureFullPage`,
+    /// along with their index
+    ///
+    /// The image can be saved with [`ImageInfo::to_png`] or shown with [`ImageInfo::to_handle`]
+    pub fn on_capture(mut self, on_capture: impl Fn(u32, ImageInfo) -> Message + 'static) -> Self {
+        self.on_capture = Some(Box::new(on_capture));
+        self
+    }
+
+    /// Subscribe to the outcome of `Action::Find` in the current view,
+    /// with the selected match and how many matches there are
+    pub fn on_find_result(
+        mut self,
+        on_find_result: impl Fn(FindResult) -> Message + 'static,
+    ) -> Self {
+        self.on_find_result = Some(Box::new(on_find_result));
+        self
+    }
+
+    /// Subscribe to errors, such as actions sent to views that have already been closed
+    pub fn on_error(mut self, on_error: impl Fn(Error) -> Message + 'static) -> Self {
+        self.on_error = Some(Box::new(on_error));
+        self
+    }
+
     /// Passes update to webview
     pub fn update(&mut self, action: Action) -> Task<Message> {
         let mut tasks = Vec::new();
 
-        if self.current_view_index.is_some() {
+        if let Err(error) = self.handle_action(action, &mut tasks) {
+            if let Some(on_error) = &self.on_error {
+                tasks.push(Task::done(on_error(error)));
+            }
+        }
+
+        // only the current view's events are reported, like its url and title
+        let current_view_id = self.get_current_view_id().ok();
+        for (id, event) in self.engine.take_events() {
+            #[cfg(feature = "log")]
+            if let ViewEvent::ConsoleMessage(message) = &event {
+                if self.log_console_messages {
+                    message.log(id);
+                }
+            }
+            match event {
+                // requests, dialogs, navigations and new windows are handled whichever view made them
+                ViewEvent::Request(request_id, request) => {
+                    match self.request_task(id, request_id, request) {
+                        Ok(task) => tasks.push(task),
+                        Err(error) => {
+                            tasks.extend(self.on_error.as_ref().map(|on| Task::done(on(error))))
+                        }
+                    }
+                }
+                ViewEvent::NavigationRequested(url, kind) => {
+                    if let Err(error) = self.navigate(id, url, kind, &mut tasks) {
+                        tasks.extend(self.on_error.as_ref().map(|on| Task::done(on(error))))
+                    }
+                }
+                ViewEvent::DialogRequested(dialog_id, dialog) => {
+                    match self.dialog_message(id, dialog_id, dialog) {
+                        Ok(message) => tasks.extend(message.map(Task::done)),
+                        Err(error) => {
+                            tasks.extend(self.on_error.as_ref().map(|on| Task::done(on(error))))
+                        }
+                    }
+                }
+                // new windows open as new views in the opener's session, like with `Action::CreateView`
+                ViewEvent::NewWindowRequested(url, _opener) => {
+                    let result = self.engine.get_session(id).and_then(|session| {
+                        let action = Action::CreateView(PageType::Url(url), session);
+                        self.handle_action(action, &mut tasks)
+                    });
+                    if let Err(error) = result {
+                        tasks.extend(self.on_error.as_ref().map(|on| Task::done(on(error))))
+                    }
+                }
+                // captures are reported for whichever view they were asked of
+                ViewEvent::FullPageCaptured(image) => {
+                    let index = self.view_ids.iter().position(|view_id| *view_id == id);
+                    if let (Some(on_capture), Some(index)) = (&self.on_capture, index) {
+                        tasks.push(Task::done(on_capture(index as u32, image)));
+                    }
+                }
+                event if Some(id) == current_view_id => {
+                    tasks.extend(self.view_event_message(event).map(Task::done))
+                }
+                _ => (),
+            }
+        }
+
+        for cleared in self.engine.take_cleared_data() {
+            match cleared {
+                Ok(()) => tasks.extend(self.on_data_cleared.clone().map(Task::done)),
+                Err(error) => tasks.extend(self.on_error.as_ref().map(|on| Task::done(on(error)))),
+            }
+        }
+
+        Task::batch(tasks)
+    }
+
+    /// Asks `on_navigation_request` whether a view may navigate, and carries out its decision
+    fn navigate(
+        &mut self,
+        id: ViewId,
+        url: String,
+        kind: NavigationKind,
+        tasks: &mut Vec<Task<Message>>,
+    ) -> Result<(), Error> {
+        let decision = match (&self.on_navigation_request, Url::parse(&url)) {
+            (Some(on), Ok(url)) => on(url, kind),
+            _ => NavigationDecision::Allow,
+        };
+        match decision {
+            NavigationDecision::Allow => self.engine.goto(id, PageType::Url(url)),
+            NavigationDecision::Cancel => Ok(()),
+            // in the same session as the view it came from
+            NavigationDecision::OpenInNewView => {
+                let session = self.engine.get_session(id)?;
+                self.handle_action(Action::CreateView(PageType::Url(url), session), tasks)
+            }
+            NavigationDecision::HandOff(message) => {
+                tasks.push(Task::done(message));
+                Ok(())
+            }
+        }
+    }
+
+    /// Hands a dialog to `on_dialog_request`, or dismisses it if there is none
+    fn dialog_message(
+        &mut self,
+        id: ViewId,
+        dialog_id: DialogId,
+        dialog: Dialog,
+    ) -> Result<Option<Message>, Error> {
+        match &self.on_dialog_request {
+            Some(on) => {
+                let request = DialogRequest::new(id, dialog_id, dialog);
+                Ok(Some(on(request)))
+            }
+            None => {
+                self.engine
+                    .answer_dialog(id, dialog_id, DialogAnswer::Dismiss)?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Runs the scheme handler for a request, or turns the request away if there is none
+    fn request_task(
+        &mut self,
+        id: ViewId,
+        request_id: RequestId,
+        request: Request,
+    ) -> Result<Task<Message>, Error> {
+        match &self.scheme_handler {
+            Some(handler) => Ok(handler.handle(request, move |response| {
+                Action::Respond(id, request_id, response)
+            })),
+            None => {
+                self.engine.respond(id, request_id, Response::not_found())?;
+                Ok(Task::none())
+            }
+        }
+    }
+
+    /// Zooms a view, reporting the new zoom to `on_zoom_change` if it changed
+    fn zoom(&mut self, id: ViewId, zoom: f32, tasks: &mut Vec<Task<Message>>) -> Result<(), Error> {
+        let zoom = clamp_zoom(zoom);
+        if self.engine.get_zoom(id)? != zoom {
+            self.engine.set_zoom(id, zoom)?;
+            if let Some(on_zoom_change) = &self.on_zoom_change {
+                tasks.push(Task::done(on_zoom_change(zoom)));
+            }
+        }
+        Ok(())
+    }
+
+    fn view_event_message(&self, event: ViewEvent) -> Option<Message> {
+        match event {
+            ViewEvent::LoadStarted(url) => self.on_load_start.as_ref().map(|on| on(url)),
+            ViewEvent::DomReady(url) => self.on_dom_ready.as_ref().map(|on| on(url)),
+            ViewEvent::LoadFinished(url) => self.on_load_finish.as_ref().map(|on| on(url)),
+            ViewEvent::LoadFailed(failure) => self.on_load_fail.as_ref().map(|on| on(failure)),
+            ViewEvent::HistoryChanged => self.on_history_change.clone(),
+            ViewEvent::ScriptEvaluated(result) => {
+                self.on_script_result.as_ref().map(|on| on(result))
+            }
+            ViewEvent::Message(message) => self.on_message.as_ref().map(|on| on(message)),
+            ViewEvent::ConsoleMessage(message) => {
+                self.on_console_message.as_ref().map(|on| on(message))
+            }
+            ViewEvent::FindResult(result) => self.on_find_result.as_ref().map(|on| on(result)),
+            // handled in update instead
+            ViewEvent::Request(..)
+            | ViewEvent::DialogRequested(..)
+            | ViewEvent::NavigationRequested(..)
+            | ViewEvent::NewWindowRequested(..)
+            | ViewEvent::FullPageCaptured(..) => None,
+        }
+    }
+
+    fn handle_action(
+        &mut self,
+        action: Action,
+        tasks: &mut Vec<Task<Message>>,
+    ) -> Result<(), Error> {
+        // a failed poll is skipped, so it can't keep the action from running
+        if let Ok(id) = self.get_current_view_id() {
             if let Some(on_url_change) = &self.on_url_change {
-                let url = self.engine.get_url(self.get_current_view_id());
-                if self.url != url {
-                    self.url = url.clone();
-                    tasks.push(Task::done(on_url_change(url)))
+                if let Ok(url) = self.engine.get_url(id) {
+                    if self.url != url {
+                        self.url = url.clone();
+                        tasks.push(Task::done(on_url_change(url)))
+                    }
                 }
             }
             if let Some(on_title_change) = &self.on_title_change {
-                let title = self.engine.get_title(self.get_current_view_id());
-                if self.title != title {
-                    self.title = title.clone();
-                    tasks.push(Task::done(on_title_change(title)))
+                if let Ok(title) = self.engine.get_title(id) {
+                    if self.title != title {
+                        self.title = title.clone();
+                        tasks.push(Task::done(on_title_change(title)))
+                    }
                 }
             }
         }
 
         match action {
             Action::ChangeView(index) => {
-                // TODO: get around new views not rendering??
-                {
-                    self.view_size.width += 10;
-                    self.view_size.height -= 10;
-                    self.engine.resize(self.view_size);
-                    self.view_size.width -= 10;
-                    self.view_size.height += 10;
-                    self.engine.resize(self.view_size);
-                    self.engine
-                        .request_render(self.index_as_view_id(index), self.view_size);
+                let id = self.index_as_view_id(index)?;
+                // the widget stays focused, so hand focus over to the new view
+                if let Ok(current_id) = self.get_current_view_id() {
+                    if current_id != id && self.engine.has_focus(current_id)? {
+                        self.engine.unfocus(current_id)?;
+                        self.engine.focus(id)?;
+                    }
+                }
+                // views that were not shown missed resizes, and have no frame at the new size yet
+                self.engine.resize(id, self.view_size)?;
+                self.engine.request_render(id)?;
+                if let Some(on_zoom_change) = &self.on_zoom_change {
+                    let zoom = self.engine.get_zoom(id)?;
+                    let current_zoom = self
+                        .get_current_view_id()
+                        .and_then(|current_id| self.engine.get_zoom(current_id));
+                    if current_zoom.ok() != Some(zoom) {
+                        tasks.push(Task::done(on_zoom_change(zoom)));
+                    }
                 }
                 self.current_view_index = Some(index as usize);
             }
             Action::CloseCurrentView => {
-                self.engine.remove_view(self.get_current_view_id());
-                self.view_ids.remove(self.current_view_index.expect(
-                    "The current view index is not currently set. Ensure you call the Action prior",
-                ));
+                let index = self.current_view_index.ok_or(Error::NoCurrentView)?;
+                self.engine.remove_view(self.get_current_view_id()?)?;
+                self.remove_view_index(index);
                 if let Some(on_view_close) = &self.on_close_view {
                     tasks.push(Task::done(on_view_close.clone()));
                 }
             }
             Action::CloseView(index) => {
-                self.engine.remove_view(self.index_as_view_id(index));
-                self.view_ids.remove(index as usize);
+                self.engine.remove_view(self.index_as_view_id(index)?)?;
+                self.remove_view_index(index as usize);
 
                 if let Some(on_view_close) = &self.on_close_view {
                     tasks.push(Task::done(on_view_close.clone()))
                 }
             }
-            Action::CreateView(page_type) => {
-                let id = self.engine.new_view(self.view_size, Some(page_type));
+            Action::CreateView(page_type, session) => {
+                let id = self
+                    .engine
+                    .new_view(self.view_size, Some(page_type), session)?;
                 self.view_ids.push(id);
+                self.engine.set_scale_factor(id, self.scale_factor)?;
 
                 if let Some(on_view_create) = &self.on_create_view {
                     tasks.push(Task::done(on_view_create.clone()))
                 }
             }
+            Action::Focus => {
+                self.engine.focus(self.get_current_view_id()?)?;
+            }
+            Action::Unfocus => {
+                self.engine.unfocus(self.get_current_view_id()?)?;
+            }
             Action::GoBackward => {
-                self.engine.go_back(self.get_current_view_id());
+                self.engine.go_back(self.get_current_view_id()?)?;
             }
             Action::GoForward => {
-                self.engine.go_forward(self.get_current_view_id());
+                self.engine.go_forward(self.get_current_view_id()?)?;
             }
             Action::GoToUrl(url) => {
-                self.engine
-                    .goto(self.get_current_view_id(), PageType::Url(url.to_string()));
+                let id = self.get_current_view_id()?;
+                self.navigate(id, url.to_string(), NavigationKind::GoTo, tasks)?;
             }
             Action::Refresh => {
-                self.engine.refresh(self.get_current_view_id());
+                self.engine.refresh(self.get_current_view_id()?)?;
             }
             Action::SendKeyboardEvent(event) => {
                 self.engine
-                    .handle_keyboard_event(self.get_current_view_id(), event);
+                    .handle_keyboard_event(self.get_current_view_id()?, event)?;
             }
             Action::SendMouseEvent(point, event) => {
                 self.engine
-                    .handle_mouse_event(self.get_current_view_id(), event, point);
+                    .handle_mouse_event(self.get_current_view_id()?, event, point)?;
+            }
+            Action::EvaluateJavaScript(script) => {
+                self.engine
+                    .evaluate_script(self.get_current_view_id()?, &script)?;
+            }
+            Action::PostMessage(message) => {
+                self.engine
+                    .post_message(self.get_current_view_id()?, &message)?;
+            }
+            Action::Respond(id, request, response) => {
+                self.engine.respond(id, request, response)?;
+            }
+            Action::AnswerDialog(responder, answer) => {
+                self.engine
+                    .answer_dialog(responder.view, responder.dialog, answer)?;
+            }
+            Action::ClearData(session, data) => {
+                self.engine.clear_data(session, data);
             }
             Action::Update => {
                 self.engine.update();
                 if self.current_view_index.is_some() {
-                    self.engine
-                        .request_render(self.get_current_view_id(), self.view_size);
+                    self.engine.request_render(self.get_current_view_id()?)?;
                 }
-                return Task::batch(tasks);
+                return Ok(());
             }
             Action::Resize(size) => {
+                // only the shown view is resized, others catch up when they are changed to
                 self.view_size = size;
-                self.engine.resize(size);
+                if self.current_view_index.is_some() {
+                    self.engine.resize(self.get_current_view_id()?, size)?;
+                }
+            }
+            Action::SetScaleFactor(scale_factor) => {
+                self.scale_factor = scale_factor;
+                for id in self.view_ids.iter() {
+                    self.engine.set_scale_factor(*id, scale_factor)?;
+                }
+            }
+            Action::SetZoom(zoom) => {
+                self.zoom(self.get_current_view_id()?, zoom, tasks)?;
+            }
+            Action::ZoomIn => {
+                let id = self.get_current_view_id()?;
+                self.zoom(id, zoom_in(self.engine.get_zoom(id)?), tasks)?;
+            }
+            Action::ZoomOut => {
+                let id = self.get_current_view_id()?;
+                self.zoom(id, zoom_out(self.engine.get_zoom(id)?), tasks)?;
+            }
+            Action::ResetZoom => {
+                self.zoom(self.get_current_view_id()?, 1.0, tasks)?;
+            }
+            Action::CaptureView(index) => {
+                let id = self.index_as_view_id(index)?;
+                self.engine.request_render(id)?;
+                if let Some(on_capture) = &self.on_capture {
+                    let image = self.engine.get_view(id)?.clone();
+                    tasks.push(Task::done(on_capture(index, image)));
+                }
+            }
+            Action::Find {
+                query,
+                case_sensitive,
+                forward,
+            } => {
+                self.engine
+                    .find(self.get_current_view_id()?, &query, case_sensitive, forward)?;
+            }
+            Action::StopFinding => {
+                self.engine.stop_finding(self.get_current_view_id()?)?;
+            }
+            Action::CaptureFullPage(index) => {
+                self.engine
+                    .capture_full_page(self.index_as_view_id(index)?)?;
             }
         };
 
         if self.current_view_index.is_some() {
-            self.engine
-                .request_render(self.get_current_view_id(), self.view_size);
+            self.engine.request_render(self.get_current_view_id()?)?;
         }
 
-        Task::batch(tasks)
+        Ok(())
+    }
+
+    /// Keeps the engine updated,
+    /// ticking fast only while a view is loading, animating or needs paint
+    ///
+    /// When every view is idle it only checks in occasionally, so timers in pages still fire
+    pub fn subscription(&self) -> Subscription<Action> {
+        let tick = if self.engine.needs_update() {
+            BUSY_TICK
+        } else {
+            IDLE_TICK
+        };
+        Subscription::batch([
+            time::every(tick).map(|_| Action::Update),
+            self.engine.subscription().map(|_| Action::Update),
+        ])
     }
 
     /// Returns webview widget for the current view
-    pub fn view<T>(&self) -> Element<Action, T> {
-        WebViewWidget::new(
-            self.engine.get_view(self.get_current_view_id()),
-            self.engine.get_cursor(self.get_current_view_id()),
-        )
+    ///
+    /// If there is no current view, or it has been closed, a blank view is shown
+    pub fn view<T>(&self) -> Element<'_, Action, T> {
+        let view = self
+            .get_current_view_id()
+            .and_then(|id| Ok((self.engine.get_view(id)?, self.engine.get_cursor(id)?)));
+        match view {
+            Ok((image_info, cursor)) => {
+                WebViewWidget::new(self.view_size, self.scale_factor, image_info, cursor)
+            }
+            Err(_) => WebViewWidget::new(
+                self.view_size,
+                self.scale_factor,
+                &ImageInfo::blank(self.view_size.width, self.view_size.height),
+                Interaction::Idle,
+            ),
+        }
+        .zoom_shortcuts(self.zoom_shortcuts)
         .into()
     }
 }
 
-struct WebViewWidget<'a> {
-    image_info: &'a ImageInfo,
+/// Tracks whether the widget was clicked into, so only it receives keyboard input
+#[derive(Default)]
+struct State {
+    is_focused: bool,
+    // held while scrolling, to tell zooming with the wheel apart
+    modifiers: keyboard::Modifiers,
+}
+
+struct WebViewWidget {
+    // in physical pixels
+    bounds: Size<u32>,
+    scale_factor: f32,
+    image: Image<Handle>,
     cursor: Interaction,
+    zoom_shortcuts: bool,
 }
 
-impl<'a> WebViewWidget<'a> {
-    fn new(image_info: &'a ImageInfo, cursor: Interaction) -> Self {
-        Self { image_info, cursor }
+impl WebViewWidget {
+    fn new(bounds: Size<u32>, scale_factor: f32, image: &ImageInfo, cursor: Interaction) -> Self {
+        Self {
+            bounds,
+            scale_factor,
+            image: image.as_image(),
+            cursor,
+            zoom_shortcuts: false,
+        }
+    }
+
+    fn zoom_shortcuts(mut self, zoom_shortcuts: bool) -> Self {
+        self.zoom_shortcuts = zoom_shortcuts;
+        self
+    }
+
+    fn zoom_action(shortcut: ZoomShortcut) -> Action {
+        match shortcut {
+            ZoomShortcut::In => Action::ZoomIn,
+            ZoomShortcut::Out => Action::ZoomOut,
+            ZoomShortcut::Reset => Action::ResetZoom,
+        }
     }
 }
 
-impl<Renderer, Theme> Widget<Action, Theme, Renderer> for WebViewWidget<'_>
+impl<Renderer, Theme> Widget<Action, Theme, Renderer> for WebViewWidget
 where
     Renderer: iced::advanced::image::Renderer<Handle = iced::advanced::image::Handle>,
 {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
     fn size(&self) -> Size<Length> {
         Size {
             width: Length::Fill,
@@ -285,7 +911,7 @@ where
         viewport: &Rectangle,
     ) {
         <Image<Handle> as Widget<Action, Theme, Renderer>>::draw(
-            &self.image_info.as_image(),
+            &self.image,
             tree,
             renderer,
             theme,
@@ -298,7 +924,7 @@ where
 
     fn on_event(
         &mut self,
-        _state: &mut Tree,
+        tree: &mut Tree,
         event: Event,
         layout: Layout<'_>,
         cursor: mouse::Cursor,
@@ -307,17 +933,48 @@ where
         shell: &mut Shell<'_, Action>,
         _viewport: &Rectangle,
     ) -> event::Status {
-        let size = Size::new(layout.bounds().width as u32, layout.bounds().height as u32);
-        if self.image_info.width != size.width || self.image_info.height != size.height {
+        let size = physical_size(layout.bounds(), self.scale_factor);
+        if self.bounds != size {
             shell.publish(Action::Resize(size));
         }
 
+        let state = tree.state.downcast_mut::<State>();
+        if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+            state.modifiers = modifiers;
+        }
         match event {
-            Event::Keyboard(event) => {
-                shell.publish(Action::SendKeyboardEvent(event));
+            Event::Keyboard(event) if state.is_focused => {
+                let shortcut = ZoomShortcut::from_key(&event).filter(|_| self.zoom_shortcuts);
+                shell.publish(match shortcut {
+                    Some(shortcut) => Self::zoom_action(shortcut),
+                    None => Action::SendKeyboardEvent(event),
+                });
+                return Status::Captured;
             }
             Event::Mouse(event) => {
+                // clicking the view focuses it, clicking anywhere else unfocuses it
+                if let mouse::Event::ButtonPressed(_) = event {
+                    let is_over = cursor.is_over(layout.bounds());
+                    if state.is_focused != is_over {
+                        state.is_focused = is_over;
+                        shell.publish(if is_over {
+                            Action::Focus
+                        } else {
+                            Action::Unfocus
+                        });
+                    }
+                }
                 if let Some(point) = cursor.position_in(layout.bounds()) {
+                    // zooming with the wheel takes the place of scrolling the page
+                    if let mouse::Event::WheelScrolled { delta } = event {
+                        let shortcut = ZoomShortcut::from_wheel(delta, state.modifiers)
+                            .filter(|_| self.zoom_shortcuts);
+                        if let Some(shortcut) = shortcut {
+                            shell.publish(Self::zoom_action(shortcut));
+                            return Status::Captured;
+                        }
+                    }
+                    let (event, point) = to_physical(event, point, self.scale_factor);
                     shell.publish(Action::SendMouseEvent(event, point));
                 }
             }
@@ -342,13 +999,190 @@ where
     }
 }
 
-impl<'a, Message: 'a, Renderer, Theme> From<WebViewWidget<'a>>
-    for Element<'a, Message, Theme, Renderer>
+impl<'a, Message: 'a, Renderer, Theme> From<WebViewWidget> for Element<'a, Message, Theme, Renderer>
 where
     Renderer: advanced::Renderer + advanced::image::Renderer<Handle = advanced::image::Handle>,
-    WebViewWidget<'a>: Widget<Message, Theme, Renderer>,
+    WebViewWidget: Widget<Message, Theme, Renderer>,
 {
-    fn from(widget: WebViewWidget<'a>) -> Self {
+    fn from(widget: WebViewWidget) -> Self {
         Self::new(widget)
     }
 }
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::engines::mock::MockEvent;
+    use crate::webview::messages;
+    use crate::Mock;
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Message {
+        Created,
+        Closed,
+        Zoom(f32),
+        Captured(u32, Size<u32>),
+        Found(FindResult),
+        DataCleared,
+        Error(Error),
+    }
+
+    /// A webview reporting everything, with a number of views and the first one shown
+    fn webview(views: usize) -> WebView<Mock, Message> {
+        let mut webview = WebView::new()
+            .on_create_view(Message::Created)
+            .on_close_view(Message::Closed)
+            .on_zoom_change(Message::Zoom)
+            .on_capture(|index, image| {
+                Message::Captured(index, Size::new(image.width(), image.height()))
+            })
+            .on_find_result(Message::Found)
+            .on_data_cleared(Message::DataCleared)
+            .on_error(Message::Error);
+        for view in 0..views {
+            let page = PageType::Url(format!("https://example.com/{view}"));
+            let _ = webview.update(Action::CreateView(page, Session::Default));
+        }
+        let _ = webview.update(Action::ChangeView(0));
+        webview
+    }
+
+    #[test]
+    fn creating_a_view_reports_it() {
+        let mut webview = webview(0);
+        let page = PageType::Url("https://example.com".to_string());
+
+        let created = messages(webview.update(Action::CreateView(page, Session::Default)));
+        assert_eq!(created, [Message::Created]);
+    }
+
+    #[test]
+    fn closing_the_only_view_keeps_updating() {
+        let mut webview = webview(1);
+
+        assert_eq!(
+            messages(webview.update(Action::CloseCurrentView)),
+            [Message::Closed]
+        );
+        assert_eq!(messages(webview.update(Action::Update)), []);
+        assert_eq!(
+            messages(webview.update(Action::Focus)),
+            [Message::Error(Error::NoCurrentView)]
+        );
+    }
+
+    #[test]
+    fn closing_the_last_view_shows_its_neighbour() {
+        let mut webview = webview(3);
+        let ids = webview.engine().view_ids();
+        let _ = webview.update(Action::ChangeView(2));
+
+        assert_eq!(
+            messages(webview.update(Action::CloseCurrentView)),
+            [Message::Closed]
+        );
+        let _ = webview.update(Action::EvaluateJavaScript("1 + 1".to_string()));
+        assert_eq!(webview.engine().scripts(ids[1]).unwrap(), ["1 + 1"]);
+    }
+
+    #[test]
+    fn closing_an_earlier_view_keeps_the_current_one() {
+        let mut webview = webview(3);
+        let ids = webview.engine().view_ids();
+        let _ = webview.update(Action::ChangeView(1));
+
+        assert_eq!(
+            messages(webview.update(Action::CloseView(0))),
+            [Message::Closed]
+        );
+        let _ = webview.update(Action::EvaluateJavaScript("1 + 1".to_string()));
+        assert_eq!(webview.engine().scripts(ids[1]).unwrap(), ["1 + 1"]);
+    }
+
+    #[test]
+    fn changing_view_reports_its_zoom() {
+        let mut webview = webview(2);
+
+        assert_eq!(
+            messages(webview.update(Action::SetZoom(2.0))),
+            [Message::Zoom(2.0)]
+        );
+        assert_eq!(
+            messages(webview.update(Action::ChangeView(1))),
+            [Message::Zoom(1.0)]
+        );
+        assert_eq!(
+            messages(webview.update(Action::ZoomIn)),
+            [Message::Zoom(zoom_in(1.0))]
+        );
+        assert_eq!(
+            messages(webview.update(Action::ChangeView(0))),
+            [Message::Zoom(2.0)]
+        );
+    }
+
+    #[test]
+    fn find_reports_the_result() {
+        let mut webview = webview(1);
+        let id = webview.engine().view_ids()[0];
+        webview.engine_mut().set_find_result(id, 2, 5).unwrap();
+
+        let find = Action::Find {
+            query: "iced".to_string(),
+            case_sensitive: true,
+            forward: false,
+        };
+        let found = FindResult {
+            query: "iced".to_string(),
+            case_sensitive: true,
+            active_match: 2,
+            total_matches: 5,
+        };
+        assert_eq!(messages(webview.update(find)), [Message::Found(found)]);
+        let _ = webview.update(Action::StopFinding);
+        assert_eq!(
+            webview.engine().events(id).unwrap(),
+            [
+                MockEvent::Find {
+                    query: "iced".to_string(),
+                    case_sensitive: true,
+                    forward: false,
+                },
+                MockEvent::StopFinding,
+            ]
+        );
+    }
+
+    #[test]
+    fn capturing_sends_the_view() {
+        let mut webview = webview(2);
+        let size = Size::new(1920, 1080);
+
+        assert_eq!(
+            messages(webview.update(Action::CaptureView(1))),
+            [Message::Captured(1, size)]
+        );
+        assert_eq!(
+            messages(webview.update(Action::CaptureFullPage(1))),
+            [Message::Captured(1, size)]
+        );
+    }
+
+    #[test]
+    fn clearing_data_reports_it() {
+        let mut webview = webview(1);
+
+        assert_eq!(
+            messages(webview.update(Action::ClearData(None, BrowsingData::ALL))),
+            [Message::DataCleared]
+        );
+        assert_eq!(webview.engine().cleared_data(), [(None, BrowsingData::ALL)]);
+
+        let error = Error::Unsupported("clearing".to_string());
+        webview.engine_mut().fail_clearing_data(error.clone());
+        assert_eq!(
+            messages(webview.update(Action::ClearData(None, BrowsingData::ALL))),
+            [Message::Error(error)]
+        );
+    }
+}